@@ -0,0 +1,56 @@
+#!/bin/sh
+# Runs tmenu with --backend layer-shell in a headless sway.
+#
+# Needs sway and, to accept the menu like a user, wtype. Without a GPU, Mesa's
+# software drivers render the menu. TMENU overrides the binary under test.
+set -eu
+
+cd "$(dirname "$0")/.."
+if [ -z "${TMENU:-}" ]; then
+    cargo build --quiet
+    TMENU=target/debug/tmenu2
+fi
+
+runtime=$(mktemp -d)
+trap 'kill "$sway" 2>/dev/null || true; rm -rf "$runtime"' EXIT
+export XDG_RUNTIME_DIR="$runtime"
+unset DISPLAY WAYLAND_DISPLAY SWAYSOCK
+
+WLR_BACKENDS=headless WLR_LIBINPUT_NO_DEVICES=1 WLR_RENDERER=pixman \
+    sway -c /dev/null >"$runtime/sway.log" 2>&1 &
+sway=$!
+for _ in $(seq 50); do
+    socket=$(find "$runtime" -maxdepth 1 -name 'wayland-*' ! -name '*.lock' | head -n 1)
+    [ -n "$socket" ] && break
+    sleep 0.1
+done
+if [ -z "$socket" ]; then
+    echo "sway did not start:" >&2
+    cat "$runtime/sway.log" >&2
+    exit 1
+fi
+export WAYLAND_DISPLAY="${socket##*/}"
+
+printf 'one\ntwo\nthree\n' | "$TMENU" --backend layer-shell --verbose >"$runtime/out" &
+menu=$!
+sleep 1
+if ! kill -0 "$menu" 2>/dev/null; then
+    wait "$menu" || true
+    echo "tmenu exited before the menu was accepted" >&2
+    exit 1
+fi
+
+if command -v wtype >/dev/null; then
+    # the layer surface has the keyboard focus exclusively
+    wtype -k Return
+    wait "$menu"
+    output=$(cat "$runtime/out")
+    if [ "$output" != "one" ]; then
+        echo "expected 'one', got '$output'" >&2
+        exit 1
+    fi
+else
+    echo "wtype not found, only checked that the menu starts" >&2
+    kill "$menu"
+fi
+echo "layer shell backend ok"
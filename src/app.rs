@@ -0,0 +1,322 @@
+use std::io::Write;
+
+use iced_core::keyboard::Event;
+use iced_core::{Color, Length, Padding};
+use iced_native::widget::Space;
+use iced_wgpu::{text_input, Column, Container, Row, Rule, Text, TextInput};
+use iced_winit::{Application, Command, Program, Subscription};
+
+use crate::item_bar::ItemBar;
+use crate::layout::{bar_window, Layout};
+use crate::output::{Output, OutputFormat};
+use crate::stdin_reader::StdinReader;
+use crate::styles;
+use crate::theme::{Separator, Theme};
+use crate::tmenu::{zeroize, MainAction, TMenu};
+use crate::tmenu_settings::TMenuSettings;
+
+type Element<'a, Message> = iced_winit::Element<'a, Message, iced_wgpu::Renderer>;
+
+/// The iced program, that shows a [`TMenu`] and prints its selections
+#[derive(Debug)]
+pub struct TMenuApp {
+    menu: TMenu,
+    output: Output,
+    output_format: OutputFormat,
+    prompt: Option<String>,
+    placeholder: String,
+    theme: Theme,
+    /// Position of the first item shown in the list and grid layouts
+    scroll: usize,
+
+    text_input: text_input::State,
+}
+
+impl TMenuApp {
+    fn print_output(&mut self) {
+        for mut selection in self.menu.take_output() {
+            match &self.output {
+                Output::Stdout => {
+                    let mut stdout = std::io::stdout();
+                    for mut line in selection.format(self.output_format) {
+                        // the process exits without flushing stdout, once the menu closes
+                        if let Err(e) = stdout
+                            .write_all(line.as_bytes())
+                            .and_then(|_| stdout.flush())
+                        {
+                            eprintln!("Failed writing selection: {}", e);
+                        }
+                        if self.menu.is_password() {
+                            zeroize(&mut line);
+                        }
+                    }
+                    if self.menu.is_password() {
+                        zeroize(&mut selection.input);
+                    }
+                }
+                Output::Collect(selections) => selections.borrow_mut().push(selection),
+            }
+        }
+    }
+}
+
+impl Program for TMenuApp {
+    type Renderer = iced_wgpu::Renderer;
+    type Message = MainAction;
+
+    fn update(&mut self, message: MainAction) -> Command<Self::Message> {
+        self.text_input.focus();
+        self.menu.update(message);
+        if self.menu.take_input_replaced() {
+            self.text_input.move_cursor_to_end();
+        }
+        self.print_output();
+
+        Command::none()
+    }
+
+    fn view(&mut self) -> Element<'_, Self::Message> {
+        let menu = &self.menu;
+        let active = menu.active_position();
+        let layout = menu.layout();
+        let visible = menu.visible_count();
+        let (first, shown) = match layout {
+            // the item bar pages the items of its window itself
+            Layout::Bar => {
+                let window = bar_window(active, visible);
+                (window.start, window.len())
+            }
+            layout => {
+                self.scroll = layout.scroll(self.scroll, active, visible);
+                (self.scroll, layout.page_size())
+            }
+        };
+        let theme = &self.theme;
+
+        let mut main_input = TextInput::new(
+            &mut self.text_input,
+            &self.placeholder,
+            menu.input(),
+            |input| MainAction::TextChanged(input),
+        )
+        .padding(Padding {
+            top: 5,
+            right: 0,
+            bottom: 5,
+            left: 0,
+        })
+        .style(styles::Input {
+            foreground: theme.foreground,
+            placeholder: theme.placeholder,
+            selection: theme.selected_background,
+        });
+        if menu.is_password() {
+            main_input = main_input.password();
+        }
+
+        let input_container = Container::new(main_input)
+            .height(Length::Units(theme.item_height))
+            .padding(styles::TEXT_INPUT_PADDING);
+        let (input_container, item_width) = match layout {
+            Layout::Bar => (
+                input_container.width(Length::Units(300)).max_width(300),
+                Length::Shrink,
+            ),
+            Layout::List { .. } | Layout::Grid { .. } => {
+                (input_container.width(Length::Fill), Length::Fill)
+            }
+        };
+        // the prompt takes its width from the row, before the items are laid out
+        let mut input_row = Row::new();
+        if let Some(prompt) = &self.prompt {
+            input_row = input_row.push(prompt_label(prompt, theme));
+        }
+        let mut input_row = input_row.push(input_container);
+        if let Some(error) = menu.filter_error() {
+            input_row = input_row.push(
+                Container::new(Text::new(error).color(theme.error))
+                    .height(Length::Units(theme.item_height))
+                    .center_y()
+                    .padding(styles::TEXT_INPUT_PADDING),
+            );
+        }
+        if menu.is_loading() {
+            input_row = input_row.push(
+                Container::new(Text::new("loading…").color(theme.hint))
+                    .height(Length::Units(theme.item_height))
+                    .center_y()
+                    .padding(styles::TEXT_INPUT_PADDING),
+            );
+        }
+
+        let items = menu
+            .visible_items()
+            .skip(first)
+            .take(shown)
+            .flat_map(|i| i.view(theme, item_width));
+
+        let content: Element<'_, Self::Message> = match layout {
+            Layout::Bar => {
+                let bar = ItemBar::new(
+                    items.collect(),
+                    || separator(theme),
+                    active.map(|position| position - first),
+                    (first > 0, first + shown < visible),
+                    overflow_indicator("<", theme),
+                    overflow_indicator(">", theme),
+                );
+                input_row.push(bar).into()
+            }
+            Layout::List { .. } => items
+                .fold(Column::new().push(input_row), |c, i| c.push(i))
+                .into(),
+            Layout::Grid { columns, .. } => {
+                let columns = columns as usize;
+                let mut grid = Column::new().push(input_row);
+                let mut row = Row::new();
+                let mut in_row = 0;
+                for item in items {
+                    row = row.push(item);
+                    in_row += 1;
+                    if in_row == columns {
+                        grid = grid.push(row);
+                        row = Row::new();
+                        in_row = 0;
+                    }
+                }
+                if in_row > 0 {
+                    // keep the cells of the last row aligned with the rows above
+                    for _ in in_row..columns {
+                        row = row.push(Space::with_width(Length::Fill));
+                    }
+                    grid = grid.push(row);
+                }
+                grid.into()
+            }
+        };
+
+        Container::new(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(styles::Window {
+                foreground: theme.foreground,
+            })
+            .into()
+    }
+}
+
+impl Application for TMenuApp {
+    type Flags = TMenuSettings;
+
+    fn new(mut flags: TMenuSettings) -> (Self, Command<Self::Message>) {
+        let prompt = flags.prompt.take();
+        let placeholder = std::mem::take(&mut flags.placeholder);
+        let theme = std::mem::take(&mut flags.theme);
+        let output = flags.output.clone();
+        let output_format = flags.output_format;
+        let mut menu = TMenu::new(flags);
+        let mut text_input = text_input::State::focused();
+        if menu.take_input_replaced() {
+            text_input.move_cursor_to_end();
+        }
+
+        let app = TMenuApp {
+            menu,
+            output,
+            output_format,
+            prompt,
+            placeholder,
+            theme,
+            scroll: 0,
+            text_input,
+        };
+        (app, Command::none())
+    }
+
+    fn title(&self) -> String {
+        "tmenu".to_string()
+    }
+
+    fn background_color(&self) -> Color {
+        self.theme.background
+    }
+
+    fn subscription(&self) -> Subscription<Self::Message> {
+        let keyboard = iced_native::subscription::events_with(global_keyboard_handler);
+        if self.menu.is_loading() {
+            Subscription::batch(vec![keyboard, Subscription::from_recipe(StdinReader)])
+        } else {
+            keyboard
+        }
+    }
+
+    fn should_exit(&self) -> bool {
+        self.menu.should_exit()
+    }
+}
+
+/// The separator between two items, taking `item_spacing` space
+fn separator<'a>(theme: &Theme) -> Element<'a, MainAction> {
+    match &theme.separator {
+        Separator::None => Space::with_width(Length::Units(theme.item_spacing)).into(),
+        Separator::Line => Rule::vertical(theme.item_spacing)
+            .style(styles::Separator {
+                color: theme.separator_color,
+            })
+            .into(),
+        Separator::Text(text) => {
+            Container::new(Text::new(text.clone()).color(theme.separator_color))
+                .height(Length::Units(theme.item_height))
+                .center_y()
+                .padding(Padding {
+                    top: 0,
+                    right: theme.item_spacing / 2,
+                    bottom: 0,
+                    left: theme.item_spacing / 2,
+                })
+                .into()
+        }
+    }
+}
+
+/// The prompt label, in the prompt colors of the theme
+fn prompt_label<'a>(prompt: &str, theme: &Theme) -> Element<'a, MainAction> {
+    let (foreground, background) = theme.prompt_colors();
+    Container::new(Text::new(prompt))
+        .height(Length::Units(theme.item_height))
+        .center_y()
+        .padding(styles::TEXT_INPUT_PADDING)
+        .style(styles::Prompt {
+            foreground,
+            background,
+        })
+        .into()
+}
+
+/// Shown in the bar layout, when more items precede or follow the shown items
+fn overflow_indicator<'a>(text: &str, theme: &Theme) -> Element<'a, MainAction> {
+    Container::new(Text::new(text))
+        .height(Length::Units(theme.item_height))
+        .center_y()
+        .padding(Padding {
+            top: 0,
+            right: theme.item_spacing / 2,
+            bottom: 0,
+            left: theme.item_spacing / 2,
+        })
+        .into()
+}
+
+fn global_keyboard_handler(
+    event: iced_native::Event,
+    _status: iced_native::event::Status,
+) -> Option<MainAction> {
+    match event {
+        iced_native::Event::Keyboard(Event::KeyPressed {
+            key_code,
+            modifiers,
+        }) => Some(MainAction::KeyPressed(key_code, modifiers)),
+
+        _ => Some(MainAction::Focus),
+    }
+}
@@ -0,0 +1,730 @@
+//! The command line syntax: which options exist, how they are spelled on the command line
+//! and in `TMENU_*` environment variables, and the shell completions derived from them.
+//!
+//! What the options mean is applied by `tmenu_settings`.
+
+/// The value an option takes
+#[derive(Debug, Copy, Clone)]
+pub enum Value {
+    /// The option takes no value
+    Flag,
+    /// Free text, named like `TEXT` in completions
+    Text(&'static str),
+    /// A file path
+    File,
+    /// One of the listed names
+    Choice(&'static [&'static str]),
+}
+
+/// A command line option
+#[derive(Debug)]
+pub struct OptionSpec {
+    /// The long name without dashes, options are identified by it
+    pub name: &'static str,
+    pub short: Option<char>,
+    /// dmenu style options, like `-nb`, are written with a single dash and have no long name
+    pub single_dash: bool,
+    pub value: Value,
+    /// One line description, shown by shell completions
+    pub help: &'static str,
+}
+
+impl OptionSpec {
+    const fn new(
+        name: &'static str,
+        short: Option<char>,
+        value: Value,
+        help: &'static str,
+    ) -> Self {
+        OptionSpec {
+            name,
+            short,
+            single_dash: false,
+            value,
+            help,
+        }
+    }
+
+    const fn dmenu(name: &'static str, value: Value, help: &'static str) -> Self {
+        OptionSpec {
+            name,
+            short: None,
+            single_dash: true,
+            value,
+            help,
+        }
+    }
+
+    pub fn takes_value(&self) -> bool {
+        !matches!(self.value, Value::Flag)
+    }
+
+    /// The environment variable, that sets the option unless given on the command line,
+    /// like `TMENU_CASE_INSENSITIVE` for `--case-insensitive`
+    pub fn env_name(&self) -> Option<String> {
+        match self.name {
+            _ if self.single_dash => None,
+            "help" | "completions" | "version" => None,
+            name => Some(format!("TMENU_{}", name.to_uppercase().replace('-', "_"))),
+        }
+    }
+}
+
+const SHELLS: &[&str] = &["bash", "zsh", "fish"];
+
+pub(crate) const OPTIONS: &[OptionSpec] = &[
+    OptionSpec::new(
+        "auto-accept",
+        Some('a'),
+        Value::Flag,
+        "Auto accept option when single option matches",
+    ),
+    OptionSpec::new(
+        "case-insensitive",
+        Some('i'),
+        Value::Flag,
+        "Match options case insensitive",
+    ),
+    OptionSpec::new(
+        "match-prefix",
+        Some('p'),
+        Value::Flag,
+        "Match options using starts-with matcher",
+    ),
+    OptionSpec::new(
+        "fuzzy",
+        Some('f'),
+        Value::Flag,
+        "Fuzzy match options and sort them by relevance",
+    ),
+    OptionSpec::new(
+        "match",
+        None,
+        Value::Choice(&["contains", "exact", "words", "regex"]),
+        "How the input matches options",
+    ),
+    OptionSpec::new(
+        "allow-undefined",
+        Some('u'),
+        Value::Flag,
+        "Allow users to type custom options",
+    ),
+    OptionSpec::new(
+        "password",
+        None,
+        Value::Flag,
+        "Masked input, prints the typed text",
+    ),
+    OptionSpec::new(
+        "prompt",
+        None,
+        Value::Text("TEXT"),
+        "Label shown left of the input",
+    ),
+    OptionSpec::new(
+        "placeholder",
+        None,
+        Value::Text("TEXT"),
+        "Text shown in the empty input",
+    ),
+    OptionSpec::new(
+        "query",
+        None,
+        Value::Text("TEXT"),
+        "Initial input, that filters the options",
+    ),
+    OptionSpec::new(
+        "select",
+        None,
+        Value::Text("VALUE"),
+        "Initially active option, by value or index",
+    ),
+    OptionSpec::new(
+        "multi",
+        None,
+        Value::Flag,
+        "Select multiple options with Ctrl+Space",
+    ),
+    OptionSpec::new(
+        "multi-order",
+        None,
+        Value::Choice(&["selection", "input"]),
+        "Order of the printed options with --multi",
+    ),
+    OptionSpec::new(
+        "input-format",
+        None,
+        Value::Choice(&["plain", "json", "tsv", "verbatim"]),
+        "Item format",
+    ),
+    OptionSpec::new(
+        "columns",
+        None,
+        Value::Text("COLUMNS"),
+        "TSV columns, e.g. value,text,hint",
+    ),
+    OptionSpec::new(
+        "output-format",
+        None,
+        Value::Choice(&["plain", "json", "index", "nul"]),
+        "Result format",
+    ),
+    OptionSpec::new(
+        "keys",
+        None,
+        Value::Choice(&["default", "emacs", "vim"]),
+        "Built in key bindings",
+    ),
+    OptionSpec::new("keymap", None, Value::File, "Load key bindings from a file"),
+    OptionSpec::new(
+        "bind",
+        None,
+        Value::Text("KEY=ACTION"),
+        "Bind a key, e.g. ctrl+n=next",
+    ),
+    OptionSpec::new(
+        "lines",
+        None,
+        Value::Text("N"),
+        "Show options in a vertical list of N lines",
+    ),
+    OptionSpec::new(
+        "grid",
+        None,
+        Value::Text("COLSxROWS"),
+        "Show options in a grid, e.g. 8x4",
+    ),
+    OptionSpec::new(
+        "width",
+        Some('w'),
+        Value::Text("SIZE"),
+        "Window width in pixels or percent",
+    ),
+    OptionSpec::new("height", None, Value::Text("SIZE"), "Window height"),
+    OptionSpec::new(
+        "max-width",
+        None,
+        Value::Text("SIZE"),
+        "Limits the window width",
+    ),
+    OptionSpec::new(
+        "x",
+        None,
+        Value::Text("SIZE"),
+        "Horizontal offset of the window",
+    ),
+    OptionSpec::new(
+        "y",
+        None,
+        Value::Text("SIZE"),
+        "Vertical offset of the window",
+    ),
+    OptionSpec::new(
+        "bottom",
+        None,
+        Value::Flag,
+        "Place the menu at the bottom of the screen",
+    ),
+    OptionSpec::new(
+        "center",
+        None,
+        Value::Flag,
+        "Place the menu in the center of the screen",
+    ),
+    OptionSpec::new(
+        "monitor",
+        None,
+        Value::Text("MONITOR"),
+        "Monitor index or name, focused or pointer",
+    ),
+    OptionSpec::new(
+        "backend",
+        None,
+        Value::Choice(&["auto", "winit", "layer-shell"]),
+        "Window backend",
+    ),
+    OptionSpec::dmenu("nb", Value::Text("COLOR"), "Normal background color"),
+    OptionSpec::dmenu("nf", Value::Text("COLOR"), "Normal foreground color"),
+    OptionSpec::dmenu("sb", Value::Text("COLOR"), "Selected item background color"),
+    OptionSpec::dmenu("sf", Value::Text("COLOR"), "Selected item foreground color"),
+    OptionSpec::dmenu("fn", Value::Text("FONT"), "Font family or file"),
+    OptionSpec::new(
+        "history",
+        None,
+        Value::File,
+        "Rank frequently selected options first",
+    ),
+    OptionSpec::new(
+        "id",
+        None,
+        Value::Text("ID"),
+        "Use the history of the menu ID",
+    ),
+    OptionSpec::new(
+        "config",
+        None,
+        Value::File,
+        "Read options from a file instead of the default config",
+    ),
+    OptionSpec::new("no-config", None, Value::Flag, "Ignore the config file"),
+    OptionSpec::new(
+        "profile",
+        None,
+        Value::Text("NAME"),
+        "Apply the options of a config file profile",
+    ),
+    OptionSpec::new(
+        "verbose",
+        Some('v'),
+        Value::Flag,
+        "More verbose log output on stderr",
+    ),
+    OptionSpec::new(
+        "dmenu",
+        None,
+        Value::Flag,
+        "Accept the options of dmenu and behave like it",
+    ),
+    OptionSpec::new(
+        "completions",
+        None,
+        Value::Choice(SHELLS),
+        "Print the shell completion script",
+    ),
+    OptionSpec::new("version", None, Value::Flag, "Print the version"),
+    OptionSpec::new(
+        "help",
+        Some('h'),
+        Value::Flag,
+        "Print help, --help for more details",
+    ),
+];
+
+/// What a dmenu option does in tmenu
+#[derive(Debug, Clone, Copy)]
+enum DmenuOption {
+    /// Stands for the option of this name
+    Maps(&'static str),
+    /// Needs nothing to be done, like `-f`: tmenu always reads stdin while it is shown
+    NoOp,
+    /// Takes a value, but the feature is not supported, a warning is printed
+    Unsupported(&'static str),
+}
+
+/// dmenu options and what they do in tmenu
+const DMENU_OPTIONS: &[(&str, DmenuOption)] = &[
+    ("b", DmenuOption::Maps("bottom")),
+    ("i", DmenuOption::Maps("case-insensitive")),
+    ("l", DmenuOption::Maps("lines")),
+    ("p", DmenuOption::Maps("prompt")),
+    ("m", DmenuOption::Maps("monitor")),
+    ("v", DmenuOption::Maps("version")),
+    ("fn", DmenuOption::Maps("fn")),
+    ("nb", DmenuOption::Maps("nb")),
+    ("nf", DmenuOption::Maps("nf")),
+    ("sb", DmenuOption::Maps("sb")),
+    ("sf", DmenuOption::Maps("sf")),
+    ("f", DmenuOption::NoOp),
+    (
+        "w",
+        DmenuOption::Unsupported("embedding into another window is not supported"),
+    ),
+];
+
+/// An option and its value, `None` for flags
+pub type Arg = (&'static OptionSpec, Option<String>);
+
+/// The options given in the environment and on the command line
+#[derive(Debug, Default)]
+pub struct ParsedArgs {
+    /// Options in the order they apply: environment variables first, then the command line
+    pub options: Vec<Arg>,
+    /// `-h` and `--help` differ, so the spelling of help is kept
+    pub long_help: bool,
+    /// Short options are read like dmenu options, see [`DMENU_OPTIONS`]
+    pub dmenu: bool,
+    /// Arguments following `--`, `None` when there is no `--`
+    pub items: Option<Vec<String>>,
+}
+
+/// Parses the command line arguments without the program name.
+///
+/// Short flags can be combined like `-ai`, values follow as the next argument or are
+/// attached like `--width=50%` and `-w50%`. Underscores in long names are accepted
+/// as dashes. `env` looks up environment variables.
+///
+/// With `--dmenu`, or when `program` is `dmenu`, short options are dmenu options.
+pub fn parse(
+    program: &str,
+    args: &[String],
+    env: impl Fn(&str) -> Option<String>,
+) -> Result<ParsedArgs, String> {
+    let mut parsed = ParsedArgs::default();
+    for spec in OPTIONS {
+        if let Some(value) = spec
+            .env_name()
+            .and_then(|name| env_value(spec, &name, &env).transpose())
+        {
+            parsed.options.push((spec, value?));
+        }
+    }
+
+    parsed.dmenu = program == "dmenu"
+        || parsed.options.iter().any(|(spec, _)| spec.name == "dmenu")
+        || args
+            .iter()
+            .take_while(|a| *a != "--")
+            .any(|a| a == "--dmenu");
+
+    let mut remaining = args.iter();
+    while let Some(arg) = remaining.next() {
+        if arg == "--" {
+            parsed.items = Some(remaining.cloned().collect());
+            break;
+        }
+        if let Some(long) = arg.strip_prefix("--") {
+            let (name, attached) = match long.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (long, None),
+            };
+            let spec = find_long(&name.replace('_', "-"))
+                .ok_or_else(|| format!("Unknown option: --{}", name))?;
+            let value = match (spec.takes_value(), attached) {
+                (true, Some(value)) => Some(value),
+                (true, None) => Some(next_value(spec, &mut remaining)?),
+                (false, Some(_)) => return Err(format!("--{} takes no value", spec.name)),
+                (false, None) => None,
+            };
+            parsed.long_help |= spec.name == "help";
+            parsed.options.push((spec, value));
+        } else if let Some(short) = arg.strip_prefix('-').filter(|s| !s.is_empty()) {
+            if parsed.dmenu {
+                let option = DMENU_OPTIONS
+                    .iter()
+                    .find(|(flag, _)| *flag == short)
+                    .ok_or_else(|| format!("Unknown dmenu option: {}", arg))?
+                    .1;
+                match option {
+                    DmenuOption::Maps(name) => {
+                        let spec = OPTIONS.iter().find(|o| o.name == name).unwrap();
+                        let value = if spec.takes_value() {
+                            Some(next_value(spec, &mut remaining)?)
+                        } else {
+                            None
+                        };
+                        parsed.options.push((spec, value));
+                    }
+                    DmenuOption::NoOp => {}
+                    DmenuOption::Unsupported(reason) => {
+                        let value = remaining
+                            .next()
+                            .ok_or_else(|| format!("Missing value of {}", arg))?;
+                        eprintln!("Ignoring {} {}: {}", arg, value, reason);
+                    }
+                }
+                continue;
+            }
+            if let Some(spec) = OPTIONS.iter().find(|o| o.single_dash && o.name == short) {
+                let value = next_value(spec, &mut remaining)?;
+                parsed.options.push((spec, Some(value)));
+                continue;
+            }
+            for (position, c) in short.char_indices() {
+                let spec = OPTIONS
+                    .iter()
+                    .find(|o| o.short == Some(c))
+                    .ok_or_else(|| format!("Unknown option: -{} in {}", c, arg))?;
+                if !spec.takes_value() {
+                    parsed.options.push((spec, None));
+                    continue;
+                }
+                // the rest of the argument is the value, like -w50%
+                let attached = &short[position + c.len_utf8()..];
+                let value = if attached.is_empty() {
+                    next_value(spec, &mut remaining)?
+                } else {
+                    attached.strip_prefix('=').unwrap_or(attached).to_string()
+                };
+                parsed.options.push((spec, Some(value)));
+                break;
+            }
+        } else {
+            return Err(format!("Unknown option: {}, items follow --", arg));
+        }
+    }
+    Ok(parsed)
+}
+
+fn find_long(name: &str) -> Option<&'static OptionSpec> {
+    OPTIONS.iter().find(|o| !o.single_dash && o.name == name)
+}
+
+fn next_value<'a>(
+    spec: &OptionSpec,
+    remaining: &mut impl Iterator<Item = &'a String>,
+) -> Result<String, String> {
+    remaining
+        .next()
+        .cloned()
+        .ok_or_else(|| format!("Missing value of {}", spelling(spec)))
+}
+
+/// The value of the environment variable `name`, `Some(None)` for enabled flags.
+///
+/// Empty variables are ignored, flags are enabled by 1, true, yes or on and ignored for
+/// 0, false, no or off.
+fn env_value(
+    spec: &OptionSpec,
+    name: &str,
+    env: impl Fn(&str) -> Option<String>,
+) -> Result<Option<Option<String>>, String> {
+    let value = match env(name) {
+        Some(value) if !value.is_empty() => value,
+        _ => return Ok(None),
+    };
+    if spec.takes_value() {
+        return Ok(Some(Some(value)));
+    }
+    match value.to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(Some(None)),
+        "0" | "false" | "no" | "off" => Ok(None),
+        _ => Err(format!(
+            "Invalid value of {}: {}, expected true or false",
+            name, value
+        )),
+    }
+}
+
+/// The option as written on the command line
+fn spelling(spec: &OptionSpec) -> String {
+    if spec.single_dash {
+        format!("-{}", spec.name)
+    } else {
+        format!("--{}", spec.name)
+    }
+}
+
+/// The completion script for `shell`, completing the command `program`
+pub fn completions(shell: &str, program: &str) -> Option<String> {
+    match shell {
+        "bash" => Some(bash_completions(program)),
+        "zsh" => Some(zsh_completions(program)),
+        "fish" => Some(fish_completions(program)),
+        _ => None,
+    }
+}
+
+fn bash_completions(program: &str) -> String {
+    let function = format!("_{}", program.replace(|c: char| !c.is_alphanumeric(), "_"));
+    let mut words = Vec::new();
+    let mut cases = String::new();
+    for spec in OPTIONS {
+        let mut spellings = vec![spelling(spec)];
+        spellings.extend(spec.short.map(|c| format!("-{}", c)));
+        words.extend(spellings.iter().cloned());
+        let reply = match spec.value {
+            Value::Flag => continue,
+            Value::Text(_) => "return".to_string(),
+            Value::File => "COMPREPLY=($(compgen -f -- \"$cur\")); return".to_string(),
+            Value::Choice(choices) => format!(
+                "COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return",
+                choices.join(" ")
+            ),
+        };
+        cases.push_str(&format!("        {}) {} ;;\n", spellings.join("|"), reply));
+    }
+    format!(
+        r#"{function}() {{
+    local cur prev
+    cur="${{COMP_WORDS[COMP_CWORD]}}"
+    prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+    case "$prev" in
+{cases}    esac
+    COMPREPLY=($(compgen -W "{words}" -- "$cur"))
+}}
+complete -F {function} {program}
+"#,
+        function = function,
+        cases = cases,
+        words = words.join(" "),
+        program = program,
+    )
+}
+
+fn zsh_completions(program: &str) -> String {
+    let mut script = format!("#compdef {}\n\n_arguments -s \\\n", program);
+    for spec in OPTIONS {
+        let argument = match spec.value {
+            Value::Flag => String::new(),
+            Value::Text(name) => format!(":{}: ", name),
+            Value::File => ":FILE:_files".to_string(),
+            Value::Choice(choices) => format!(":{}:({})", spec.name, choices.join(" ")),
+        };
+        let equals = if spec.takes_value() && !spec.single_dash {
+            "="
+        } else {
+            ""
+        };
+        script.push_str(&format!(
+            "    '*{}{}[{}]{}' \\\n",
+            spelling(spec),
+            equals,
+            spec.help,
+            argument
+        ));
+        if let Some(c) = spec.short {
+            script.push_str(&format!("    '*-{}[{}]{}' \\\n", c, spec.help, argument));
+        }
+    }
+    script.push_str("    '*::item: '\n");
+    script
+}
+
+fn fish_completions(program: &str) -> String {
+    let mut script = String::new();
+    for spec in OPTIONS {
+        let mut line = format!("complete -c {}", program);
+        if spec.single_dash {
+            line.push_str(&format!(" -o {}", spec.name));
+        } else {
+            line.push_str(&format!(" -l {}", spec.name));
+        }
+        if let Some(c) = spec.short {
+            line.push_str(&format!(" -s {}", c));
+        }
+        match spec.value {
+            Value::Flag => {}
+            Value::Text(_) => line.push_str(" -x"),
+            Value::File => line.push_str(" -r -F"),
+            Value::Choice(choices) => line.push_str(&format!(" -x -a '{}'", choices.join(" "))),
+        }
+        script.push_str(&format!("{} -d '{}'\n", line, spec.help));
+    }
+    script
+}
+
+#[cfg(test)]
+mod test {
+    use crate::args::{completions, parse, ParsedArgs};
+
+    fn parse_with_env(args: &[&str], env: &[(&str, &str)]) -> Result<ParsedArgs, String> {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        parse("tmenu", &args, |name| {
+            env.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    fn options(parsed: &ParsedArgs) -> Vec<(&str, Option<&str>)> {
+        parsed
+            .options
+            .iter()
+            .map(|(spec, value)| (spec.name, value.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn option_syntax() {
+        let parsed = parse_with_env(
+            &[
+                "-ai",
+                "-w50%",
+                "--prompt=Run",
+                "--lines",
+                "5",
+                "-nb",
+                "#222",
+                "--match_prefix",
+            ],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            options(&parsed),
+            [
+                ("auto-accept", None),
+                ("case-insensitive", None),
+                ("width", Some("50%")),
+                ("prompt", Some("Run")),
+                ("lines", Some("5")),
+                ("nb", Some("#222")),
+                ("match-prefix", None),
+            ]
+        );
+        assert!(parsed.items.is_none());
+
+        let parsed = parse_with_env(&["-iw", "800", "--", "-a", "b"], &[]).unwrap();
+        assert_eq!(
+            options(&parsed),
+            [("case-insensitive", None), ("width", Some("800"))]
+        );
+        assert_eq!(parsed.items.unwrap(), ["-a", "b"]);
+
+        assert!(parse_with_env(&["-ax"], &[]).is_err());
+        assert!(parse_with_env(&["--prompt"], &[]).is_err());
+        assert!(parse_with_env(&["--fuzzy=yes"], &[]).is_err());
+        assert!(parse_with_env(&["item"], &[]).is_err());
+    }
+
+    #[test]
+    fn dmenu_options() {
+        let dmenu_args = [
+            "--dmenu", "-b", "-f", "-i", "-l", "10", "-p", "Run", "-w", "0x12", "-nb", "#222", "-v",
+        ];
+        let parsed = parse_with_env(&dmenu_args, &[]).unwrap();
+        assert!(parsed.dmenu);
+        assert_eq!(
+            options(&parsed),
+            [
+                ("dmenu", None),
+                ("bottom", None),
+                ("case-insensitive", None),
+                ("lines", Some("10")),
+                ("prompt", Some("Run")),
+                ("nb", Some("#222")),
+                ("version", None),
+            ]
+        );
+        assert!(parse_with_env(&["--dmenu", "-ai"], &[]).is_err());
+        assert!(parse_with_env(&["--dmenu", "-w"], &[]).is_err());
+
+        let args: Vec<String> = ["-p", "Run"].iter().map(|a| a.to_string()).collect();
+        let parsed = parse("dmenu", &args, |_| None).unwrap();
+        assert_eq!(options(&parsed), [("prompt", Some("Run"))]);
+    }
+
+    #[test]
+    fn environment_fallbacks() {
+        let env = [
+            ("TMENU_PROMPT", "Run"),
+            ("TMENU_CASE_INSENSITIVE", "true"),
+            ("TMENU_FUZZY", "0"),
+            ("TMENU_QUERY", ""),
+        ];
+        let parsed = parse_with_env(&["--prompt", "Open"], &env).unwrap();
+        assert_eq!(
+            options(&parsed),
+            [
+                ("case-insensitive", None),
+                ("prompt", Some("Run")),
+                ("prompt", Some("Open")),
+            ]
+        );
+
+        assert!(parse_with_env(&[], &[("TMENU_MULTI", "maybe")]).is_err());
+    }
+
+    #[test]
+    fn shell_completions() {
+        let bash = completions("bash", "tmenu").unwrap();
+        assert!(bash.contains("complete -F _tmenu tmenu"));
+        assert!(bash.contains("--match) COMPREPLY=($(compgen -W \"contains exact words regex\""));
+        let zsh = completions("zsh", "tmenu").unwrap();
+        assert!(zsh.contains("'*--width=[Window width in pixels or percent]:SIZE: '"));
+        let fish = completions("fish", "tmenu").unwrap();
+        assert!(fish.contains("complete -c tmenu -l auto-accept -s a -d"));
+        assert!(fish.contains("complete -c tmenu -o nb -x -d"));
+        assert!(completions("tcsh", "tmenu").is_none());
+    }
+}
@@ -0,0 +1,353 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::error::TMenuError;
+use crate::filter::MatchMode;
+use crate::geometry::{Dimension, Geometry, Placement};
+use crate::history::history_path_for_id;
+use crate::item_parser::{parse_columns, InputFormat};
+use crate::layout::Layout;
+use crate::monitor::MonitorSelection;
+use crate::output::OutputFormat;
+use crate::theme::ThemeOptions;
+use crate::tmenu_settings::{Backend, MultiOrder, TMenuSettings};
+
+/// Settings of a config file section, named like the long command line options
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ConfigOptions {
+    auto_accept: Option<bool>,
+    case_insensitive: Option<bool>,
+    match_prefix: Option<bool>,
+    fuzzy: Option<bool>,
+    #[serde(rename = "match")]
+    match_mode: Option<String>,
+    allow_undefined: Option<bool>,
+    multi: Option<bool>,
+    multi_order: Option<String>,
+    prompt: Option<String>,
+    placeholder: Option<String>,
+    input_format: Option<String>,
+    columns: Option<String>,
+    output_format: Option<String>,
+    history: Option<PathBuf>,
+    id: Option<String>,
+    keys: Option<String>,
+    keymap: Option<PathBuf>,
+    lines: Option<u16>,
+    grid: Option<String>,
+    width: Option<Dimension>,
+    height: Option<Dimension>,
+    max_width: Option<Dimension>,
+    x: Option<Dimension>,
+    y: Option<Dimension>,
+    bottom: Option<bool>,
+    center: Option<bool>,
+    monitor: Option<MonitorSelection>,
+    backend: Option<String>,
+    #[serde(default)]
+    bind: BTreeMap<String, String>,
+    #[serde(default)]
+    theme: ThemeOptions,
+}
+
+impl ConfigOptions {
+    /// Options of `self`, overridden by the options set in `other`
+    fn merge(self, other: &ConfigOptions) -> ConfigOptions {
+        let mut bind = self.bind;
+        bind.extend(other.bind.clone());
+        ConfigOptions {
+            auto_accept: other.auto_accept.or(self.auto_accept),
+            case_insensitive: other.case_insensitive.or(self.case_insensitive),
+            match_prefix: other.match_prefix.or(self.match_prefix),
+            fuzzy: other.fuzzy.or(self.fuzzy),
+            match_mode: other.match_mode.clone().or(self.match_mode),
+            allow_undefined: other.allow_undefined.or(self.allow_undefined),
+            multi: other.multi.or(self.multi),
+            multi_order: other.multi_order.clone().or(self.multi_order),
+            prompt: other.prompt.clone().or(self.prompt),
+            placeholder: other.placeholder.clone().or(self.placeholder),
+            input_format: other.input_format.clone().or(self.input_format),
+            columns: other.columns.clone().or(self.columns),
+            output_format: other.output_format.clone().or(self.output_format),
+            history: other.history.clone().or(self.history),
+            id: other.id.clone().or(self.id),
+            keys: other.keys.clone().or(self.keys),
+            keymap: other.keymap.clone().or(self.keymap),
+            lines: other.lines.or(self.lines),
+            grid: other.grid.clone().or(self.grid),
+            width: other.width.or(self.width),
+            height: other.height.or(self.height),
+            max_width: other.max_width.or(self.max_width),
+            x: other.x.or(self.x),
+            y: other.y.or(self.y),
+            bottom: other.bottom.or(self.bottom),
+            center: other.center.or(self.center),
+            monitor: other.monitor.clone().or(self.monitor),
+            backend: other.backend.clone().or(self.backend),
+            bind,
+            theme: self.theme.merge(&other.theme),
+        }
+    }
+
+    /// Stores the configured options in `settings`
+    pub fn apply(&self, settings: &mut TMenuSettings) -> Result<(), TMenuError> {
+        let flags = [
+            (self.auto_accept, &mut settings.auto_accept),
+            (self.case_insensitive, &mut settings.case_insensitive),
+            (self.match_prefix, &mut settings.filter_by_prefix),
+            (self.fuzzy, &mut settings.fuzzy),
+            (self.allow_undefined, &mut settings.allow_undefined),
+            (self.multi, &mut settings.multi_select),
+        ];
+        for (value, flag) in flags {
+            if let Some(value) = value {
+                *flag = value;
+            }
+        }
+
+        if let Some(mode) = &self.match_mode {
+            settings.match_mode =
+                MatchMode::parse(mode).ok_or_else(|| format!("Unknown match mode: {}", mode))?;
+        }
+        if let Some(order) = &self.multi_order {
+            settings.multi_order = MultiOrder::parse(order)
+                .ok_or_else(|| format!("Unknown multi-order: {}", order))?;
+        }
+        if let Some(prompt) = &self.prompt {
+            settings.prompt = Some(prompt.clone());
+        }
+        if let Some(placeholder) = &self.placeholder {
+            settings.placeholder = placeholder.clone();
+        }
+        if let Some(format) = &self.input_format {
+            settings.input_format = InputFormat::parse(format)
+                .ok_or_else(|| format!("Unknown input-format: {}", format))?;
+        }
+        if let Some(columns) = &self.columns {
+            settings.columns = Some(parse_columns(columns)?);
+        }
+        if let Some(format) = &self.output_format {
+            settings.output_format = OutputFormat::parse(format)
+                .ok_or_else(|| format!("Unknown output-format: {}", format))?;
+        }
+        if let Some(history) = &self.history {
+            settings.history = Some(history.clone());
+        }
+        if let Some(id) = &self.id {
+            settings.history =
+                Some(history_path_for_id(id).ok_or_else(|| format!("Invalid id: {}", id))?);
+        }
+
+        if let Some(preset) = &self.keys {
+            settings.keymap.set_preset(preset)?;
+        }
+        if let Some(keymap) = &self.keymap {
+            settings.keymap.load(keymap)?;
+        }
+        for (key, action) in &self.bind {
+            settings.keymap.bind_spec(&format!("{}={}", key, action))?;
+        }
+        if let Some(lines) = self.lines {
+            settings.layout = Layout::with_lines(lines);
+        }
+        if let Some(grid) = &self.grid {
+            settings.layout = Layout::parse_grid(grid)?;
+        }
+        self.apply_geometry(&mut settings.geometry);
+        if let Some(monitor) = &self.monitor {
+            settings.monitor = monitor.clone();
+        }
+        if let Some(backend) = &self.backend {
+            settings.backend =
+                Backend::parse(backend).ok_or_else(|| format!("Unknown backend: {}", backend))?;
+        }
+        self.theme.apply(&mut settings.theme)?;
+
+        Ok(())
+    }
+
+    fn apply_geometry(&self, geometry: &mut Geometry) {
+        if self.width.is_some() {
+            geometry.width = self.width;
+        }
+        if self.height.is_some() {
+            geometry.height = self.height;
+        }
+        if self.max_width.is_some() {
+            geometry.max_width = self.max_width;
+        }
+        if let Some(x) = self.x {
+            geometry.x = x;
+        }
+        if let Some(y) = self.y {
+            geometry.y = y;
+        }
+        if self.bottom == Some(true) {
+            geometry.placement = Placement::Bottom;
+        }
+        if self.center == Some(true) {
+            geometry.placement = Placement::Center;
+        }
+    }
+}
+
+/// Content of a config file: the options of the top level table, and named profiles
+/// in `[profiles.NAME]` tables, that override them.
+#[derive(Debug, Default)]
+pub struct Config {
+    base: ConfigOptions,
+    profiles: BTreeMap<String, ConfigOptions>,
+}
+
+impl Config {
+    pub fn parse(content: &str) -> Result<Config, String> {
+        let mut table: toml::value::Table = toml::from_str(content).map_err(|e| e.to_string())?;
+        let profiles = match table.remove("profiles") {
+            Some(profiles) => profiles
+                .try_into()
+                .map_err(|e| format!("Invalid profiles: {}", e))?,
+            None => BTreeMap::new(),
+        };
+        let base = toml::Value::Table(table)
+            .try_into()
+            .map_err(|e: toml::de::Error| e.to_string())?;
+
+        Ok(Config { base, profiles })
+    }
+
+    /// Reads a config file, a missing file is `None`
+    pub fn load(path: &Path) -> Result<Option<Config>, TMenuError> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                let message = format!("Failed reading {}: {}", path.display(), e);
+                return Err(TMenuError::Io(message));
+            }
+        };
+        Config::parse(&content)
+            .map(Some)
+            .map_err(|e| TMenuError::Arguments(format!("Invalid config {}: {}", path.display(), e)))
+    }
+
+    /// The top level options, merged with the options of the profile
+    pub fn options(&self, profile: Option<&str>) -> Result<ConfigOptions, String> {
+        match profile {
+            Some(name) => self
+                .profiles
+                .get(name)
+                .map(|p| self.base.clone().merge(p))
+                .ok_or_else(|| format!("Unknown profile: {}", name)),
+            None => Ok(self.base.clone()),
+        }
+    }
+}
+
+/// `$XDG_CONFIG_HOME/tmenu/config.toml`, with `$XDG_CONFIG_HOME` defaulting to `~/.config`
+pub fn default_config_path() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|s| !s.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| Path::new(&h).join(".config")))?;
+
+    Some(config_home.join("tmenu").join("config.toml"))
+}
+
+#[cfg(test)]
+mod test {
+    use iced_core::keyboard::{KeyCode, Modifiers};
+    use iced_core::Color;
+
+    use crate::config::Config;
+    use crate::geometry::{Dimension, Placement};
+    use crate::output::OutputFormat;
+    use crate::tmenu::MainAction;
+    use crate::tmenu_settings::TMenuSettings;
+
+    const CONFIG: &str = r##"
+case-insensitive = true
+output-format = "json"
+
+[bind]
+"ctrl+n" = "next"
+
+[theme]
+background = "#222222"
+font = "Monospace:size=12"
+
+[profiles.launcher]
+auto-accept = true
+width = "40%"
+center = true
+output-format = "plain"
+keys = "vim"
+
+[profiles.launcher.bind]
+"ctrl+p" = "previous"
+
+[profiles.launcher.theme]
+font-size = 16
+"##;
+
+    #[test]
+    fn profile_overrides_base() {
+        let config = Config::parse(CONFIG).unwrap();
+
+        let mut settings = TMenuSettings::default();
+        config.options(None).unwrap().apply(&mut settings).unwrap();
+        assert!(settings.case_insensitive);
+        assert!(!settings.auto_accept);
+        assert_eq!(settings.output_format, OutputFormat::Json);
+
+        let mut settings = TMenuSettings::default();
+        let options = config.options(Some("launcher")).unwrap();
+        assert_eq!(options.bind.len(), 2);
+        options.apply(&mut settings).unwrap();
+        assert!(settings.case_insensitive);
+        assert!(settings.auto_accept);
+        assert_eq!(settings.output_format, OutputFormat::Plain);
+        assert_eq!(
+            settings.theme.background,
+            Color::from_rgb8(0x22, 0x22, 0x22)
+        );
+        assert_eq!(settings.theme.font.as_deref(), Some("Monospace"));
+        assert_eq!(settings.theme.font_size, 16);
+        assert_eq!(settings.geometry.width, Some(Dimension::Percent(40.0)));
+        assert_eq!(settings.geometry.placement, Placement::Center);
+
+        assert!(config.options(Some("unknown")).is_err());
+    }
+
+    #[test]
+    fn keys_preset_keeps_bindings() {
+        let config = Config::parse("[bind]\n\"ctrl+j\" = \"abort\"").unwrap();
+        let mut settings = TMenuSettings::default();
+        config.options(None).unwrap().apply(&mut settings).unwrap();
+        // like --keys vim on the command line, after the config is applied
+        settings.keymap.set_preset("vim").unwrap();
+        let action = settings.keymap.action(KeyCode::J, Modifiers::CTRL);
+        assert!(matches!(action, Some(MainAction::Abort)));
+        let action = settings.keymap.action(KeyCode::K, Modifiers::CTRL);
+        assert!(matches!(action, Some(MainAction::Previous)));
+        assert!(settings.keymap.set_preset("nano").is_err());
+    }
+
+    #[test]
+    fn reject_unknown_keys() {
+        let error = Config::parse("case-insensitiv = true").unwrap_err();
+        assert!(error.contains("case-insensitiv"), "{}", error);
+
+        let error = Config::parse("[profiles.x]\nfoo = 1").unwrap_err();
+        assert!(error.contains("foo"), "{}", error);
+
+        let error = Config::parse("[theme]\ncolor = \"#fff\"").unwrap_err();
+        assert!(error.contains("color"), "{}", error);
+
+        let config = Config::parse("output-format = \"xml\"").unwrap();
+        let mut settings = TMenuSettings::default();
+        assert!(config.options(None).unwrap().apply(&mut settings).is_err());
+    }
+}
@@ -0,0 +1,89 @@
+use std::fmt::{Display, Formatter};
+
+/// Why tmenu failed, as opposed to the user aborting the menu.
+///
+/// Each kind of failure has its own exit code, see [`TMenuError::exit_code`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum TMenuError {
+    /// Invalid command line arguments, config or keymap file
+    Arguments(String),
+    /// Neither options were passed, nor read from stdin
+    NoOptions,
+    /// Reading stdin or a file failed
+    Io(String),
+    /// The line of stdin with this number, starting at 1, is not valid UTF-8
+    InvalidUtf8 { line: usize },
+    /// The window, the Wayland surface or the GPU renderer could not be created
+    Window(String),
+}
+
+impl TMenuError {
+    /// The process exit code.
+    ///
+    /// 0, 1 and 3 are used for a selection, an abort and custom input.
+    ///
+    /// | code | error             |
+    /// |------|-------------------|
+    /// | 2    | no options        |
+    /// | 4    | invalid arguments |
+    /// | 5    | I/O error         |
+    /// | 6    | invalid UTF-8     |
+    /// | 7    | window or GPU     |
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            TMenuError::NoOptions => 2,
+            TMenuError::Arguments(_) => 4,
+            TMenuError::Io(_) => 5,
+            TMenuError::InvalidUtf8 { .. } => 6,
+            TMenuError::Window(_) => 7,
+        }
+    }
+}
+
+impl Display for TMenuError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TMenuError::Arguments(message) | TMenuError::Io(message) => write!(f, "{}", message),
+            TMenuError::NoOptions => write!(f, "No options available"),
+            TMenuError::InvalidUtf8 { line } => {
+                write!(f, "Line {} of the input is not valid UTF-8", line)
+            }
+            TMenuError::Window(message) => write!(f, "Failed to show the menu: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for TMenuError {}
+
+/// Validation errors of options and their values are described by a message
+impl From<String> for TMenuError {
+    fn from(message: String) -> Self {
+        TMenuError::Arguments(message)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::error::TMenuError;
+
+    #[test]
+    fn distinct_exit_codes() {
+        let errors = [
+            TMenuError::Arguments("Unknown option: -x".to_string()),
+            TMenuError::NoOptions,
+            TMenuError::Io("Failed reading stdin".to_string()),
+            TMenuError::InvalidUtf8 { line: 3 },
+            TMenuError::Window("No adapter".to_string()),
+        ];
+        let mut codes: Vec<i32> = errors.iter().map(TMenuError::exit_code).collect();
+        codes.extend([0, 1, 3]);
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(codes.len(), errors.len() + 3);
+
+        assert_eq!(
+            TMenuError::InvalidUtf8 { line: 3 }.to_string(),
+            "Line 3 of the input is not valid UTF-8"
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::filter::{Filter, FilterFactory};
+use crate::filter::{contains_match, prefix_match, Filter, FilterFactory, Match};
 use crate::filter::entry_filter::{Entry, EntryFilter};
 use crate::menu_item::MenuItem;
 
@@ -10,21 +10,7 @@ pub struct CIFactory {
 
 impl CIFactory {
     pub fn create(items: &[MenuItem], starts_width: bool) -> Self {
-        let entries = items
-            .into_iter()
-            .enumerate()
-            .map(|(index, i)| Entry {
-                mnemonic: i.mnemonic.as_deref().map(|i| i.into()),
-                value: i
-                    .value
-                    .as_deref()
-                    .map(|v| v)
-                    .unwrap_or_else(|| i.text.as_str())
-                    .to_lowercase()
-                    .into(),
-                index,
-            })
-            .collect();
+        let entries = items.iter().map(|item| Entry::create(item, true)).collect();
 
         CIFactory {
             entries,
@@ -34,6 +20,10 @@ impl CIFactory {
 }
 
 impl FilterFactory for CIFactory {
+    fn add_items(&mut self, items: &[MenuItem]) {
+        self.entries.extend(items.iter().map(|item| Entry::create(item, true)));
+    }
+
     fn create_filter<'b, 'a: 'b>(&'a mut self, input: &'b str) -> Box<dyn Filter<'b> + 'b> {
         let lower_case = input.to_lowercase();
         if self.starts_width {
@@ -71,8 +61,8 @@ impl<'a> EntryFilter for CaseInsensitiveStartsWithFilter<'a> {
         self.input
     }
 
-    fn value_match(&self, entry: &Entry) -> bool {
-        entry.value.starts_with(&self.lower_case)
+    fn value_match(&self, entry: &Entry) -> Match {
+        prefix_match(&entry.value, &self.lower_case)
     }
 }
 
@@ -93,7 +83,7 @@ impl<'a> EntryFilter for CaseInsensitiveContainsFilter<'a> {
         self.input
     }
 
-    fn value_match(&self, entry: &crate::filter::entry_filter::Entry) -> bool {
-        entry.value.contains(&self.lower_case)
+    fn value_match(&self, entry: &crate::filter::entry_filter::Entry) -> Match {
+        contains_match(&entry.value, &self.lower_case)
     }
 }
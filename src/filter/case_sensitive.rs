@@ -1,7 +1,6 @@
-use crate::filter::{
-    Filter, FilterFactory,
-};
+use crate::filter::{contains_match, prefix_match, Filter, FilterFactory, Match};
 use crate::filter::entry_filter::{Entry, EntryFilter};
+use crate::menu_item::MenuItem;
 use crate::tmenu_settings::TMenuSettings;
 
 #[derive(Debug)]
@@ -15,12 +14,7 @@ impl CSFactory {
         let acc = settings
             .available_options
             .iter()
-            .enumerate()
-            .map(|(index, i)| Entry {
-                mnemonic: i.mnemonic.as_deref().map(|s| s.into()),
-                value: i.value.as_deref().unwrap_or(i.text.as_str()).into(),
-                index,
-            })
+            .map(|item| Entry::create(item, false))
             .collect();
         CSFactory {
             acc,
@@ -30,6 +24,10 @@ impl CSFactory {
 }
 
 impl FilterFactory for CSFactory {
+    fn add_items(&mut self, items: &[MenuItem]) {
+        self.acc.extend(items.iter().map(|item| Entry::create(item, false)));
+    }
+
     fn create_filter<'b, 'a: 'b>(&'a mut self, input: &'b str) -> Box<dyn Filter<'b> + 'b> {
         if self.starts_with {
             Box::new(CaseSensitiveStartsWithFilter {
@@ -60,8 +58,8 @@ impl<'a> EntryFilter for CaseSensitiveStartsWithFilter<'a> {
         self.input
     }
 
-    fn value_match(&self, entry: &Entry) -> bool {
-        entry.value.starts_with(self.input)
+    fn value_match(&self, entry: &Entry) -> Match {
+        prefix_match(&entry.value, self.input)
     }
 }
 
@@ -80,7 +78,7 @@ impl<'a> EntryFilter for CaseSensitiveContainsFilter<'a> {
         self.input
     }
 
-    fn value_match(&self, entry: &Entry) -> bool {
-        entry.value.contains(self.input)
+    fn value_match(&self, entry: &Entry) -> Match {
+        contains_match(&entry.value, self.input)
     }
 }
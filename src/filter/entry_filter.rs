@@ -1,9 +1,27 @@
+use crate::filter::Match;
+use crate::menu_item::MenuItem;
+
 pub struct Entry {
     pub mnemonic: Option<Box<str>>,
     pub value: Box<str>,
     pub index: usize,
 }
 
+impl Entry {
+    /// The entry of `item`, with a lower case value for case insensitive matching
+    pub fn create(item: &MenuItem, case_insensitive: bool) -> Entry {
+        Entry {
+            mnemonic: item.mnemonic.as_deref().map(|m| m.into()),
+            value: if case_insensitive {
+                item.search_text().to_lowercase().into()
+            } else {
+                item.search_text().into()
+            },
+            index: item.index,
+        }
+    }
+}
+
 impl std::fmt::Debug for Entry {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -21,5 +39,5 @@ pub trait EntryFilter {
 
     fn get_input(&self) -> &str;
 
-    fn value_match(&self, entry: &Entry) -> bool;
+    fn value_match(&self, entry: &Entry) -> Match;
 }
@@ -0,0 +1,67 @@
+use crate::filter::entry_filter::{Entry, EntryFilter};
+use crate::filter::{Filter, FilterFactory, Match, SCORE_EXACT};
+use crate::menu_item::MenuItem;
+
+/// Matches items, whose value equals the whole input
+#[derive(Debug)]
+pub struct ExactFactory {
+    entries: Vec<Entry>,
+    case_insensitive: bool,
+}
+
+impl ExactFactory {
+    pub fn create(items: &[MenuItem], case_insensitive: bool) -> Self {
+        let mut factory = ExactFactory {
+            entries: Vec::with_capacity(items.len()),
+            case_insensitive,
+        };
+        factory.add_items(items);
+        factory
+    }
+}
+
+impl FilterFactory for ExactFactory {
+    fn add_items(&mut self, items: &[MenuItem]) {
+        let case_insensitive = self.case_insensitive;
+        self.entries
+            .extend(items.iter().map(|i| Entry::create(i, case_insensitive)));
+    }
+
+    fn create_filter<'b, 'a: 'b>(&'a mut self, input: &'b str) -> Box<dyn Filter<'b> + 'b> {
+        let value = if self.case_insensitive {
+            input.to_lowercase()
+        } else {
+            input.to_string()
+        };
+        Box::new(ExactFilter {
+            value,
+            input,
+            acc: &self.entries,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct ExactFilter<'a> {
+    value: String,
+    input: &'a str,
+    acc: &'a [Entry],
+}
+
+impl<'a> EntryFilter for ExactFilter<'a> {
+    fn get_acc(&self) -> &[Entry] {
+        self.acc
+    }
+
+    fn get_input(&self) -> &str {
+        self.input
+    }
+
+    fn value_match(&self, entry: &Entry) -> Match {
+        if *entry.value == self.value {
+            Match::Score(SCORE_EXACT)
+        } else {
+            Match::NoMatch
+        }
+    }
+}
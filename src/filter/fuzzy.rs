@@ -0,0 +1,222 @@
+use crate::filter::{match_mnemonic_opt, Filter, FilterFactory, Match};
+use crate::menu_item::MenuItem;
+
+const SCORE_MATCH: i32 = 16;
+const BONUS_WORD_START: i32 = 10;
+const BONUS_CAMEL_CASE: i32 = 8;
+const BONUS_CONSECUTIVE: i32 = 6;
+const PENALTY_GAP: i32 = 1;
+
+const NO_SCORE: i32 = i32::MIN / 2;
+
+pub struct FuzzyEntry {
+    mnemonic: Option<Box<str>>,
+    chars: Box<[char]>,
+    bonus: Box<[i32]>,
+}
+
+impl std::fmt::Debug for FuzzyEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "({}) '{}'",
+            self.mnemonic.as_deref().unwrap_or(""),
+            self.chars.iter().collect::<String>()
+        )
+    }
+}
+
+/// Subsequence matcher that ranks items by how well the input characters line up with
+/// word starts, camelCase humps and consecutive runs in the searched item text.
+#[derive(Debug)]
+pub struct FuzzyFactory {
+    entries: Vec<FuzzyEntry>,
+    case_insensitive: bool,
+}
+
+impl FuzzyFactory {
+    pub fn create(items: &[MenuItem], case_insensitive: bool) -> Self {
+        let mut factory = FuzzyFactory {
+            entries: Vec::with_capacity(items.len()),
+            case_insensitive,
+        };
+        factory.add_items(items);
+        factory
+    }
+
+    fn entry(&self, item: &MenuItem) -> FuzzyEntry {
+        let original: Vec<char> = item.search_text().chars().collect();
+        let bonus = char_bonus(&original);
+        let chars = if self.case_insensitive {
+            original.into_iter().map(fold_case).collect()
+        } else {
+            original.into_boxed_slice()
+        };
+        FuzzyEntry {
+            mnemonic: item.mnemonic.as_deref().map(|m| m.into()),
+            chars,
+            bonus,
+        }
+    }
+}
+
+impl FilterFactory for FuzzyFactory {
+    fn add_items(&mut self, items: &[MenuItem]) {
+        let entries: Vec<FuzzyEntry> = items.iter().map(|i| self.entry(i)).collect();
+        self.entries.extend(entries);
+    }
+
+    fn create_filter<'b, 'a: 'b>(&'a mut self, input: &'b str) -> Box<dyn Filter<'b> + 'b> {
+        let pattern = if self.case_insensitive {
+            input.chars().map(fold_case).collect()
+        } else {
+            input.chars().collect()
+        };
+        Box::new(FuzzyFilter {
+            input,
+            pattern,
+            acc: &self.entries,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct FuzzyFilter<'a> {
+    input: &'a str,
+    pattern: Vec<char>,
+    acc: &'a [FuzzyEntry],
+}
+
+impl<'a> Filter<'a> for FuzzyFilter<'a> {
+    fn match_item(&mut self, item: &MenuItem) -> Match {
+        self.acc
+            .get(item.index)
+            .map(|e| {
+                match_mnemonic_opt(e.mnemonic.as_deref(), self.input).or_else(|| {
+                    fuzzy_score(&self.pattern, &e.chars, &e.bonus)
+                        .map(|score| Match::Score(score.max(0) as u32))
+                        .unwrap_or(Match::NoMatch)
+                })
+            })
+            .unwrap_or(Match::NoMatch)
+    }
+}
+
+fn fold_case(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn char_bonus(chars: &[char]) -> Box<[i32]> {
+    let mut prev: Option<char> = None;
+    chars
+        .iter()
+        .map(|&c| {
+            let bonus = match prev {
+                None => BONUS_WORD_START,
+                Some(p) if !p.is_alphanumeric() && c.is_alphanumeric() => BONUS_WORD_START,
+                Some(p) if p.is_lowercase() && c.is_uppercase() => BONUS_CAMEL_CASE,
+                Some(p) if p.is_alphabetic() && c.is_numeric() => BONUS_CAMEL_CASE,
+                _ => 0,
+            };
+            prev = Some(c);
+            bonus
+        })
+        .collect()
+}
+
+/// Finds the best scoring alignment of `pattern` as a subsequence of `text`.
+///
+/// Returns `None`, when `pattern` is not a subsequence of `text`.
+fn fuzzy_score(pattern: &[char], text: &[char], bonus: &[i32]) -> Option<i32> {
+    if pattern.is_empty() {
+        return Some(0);
+    }
+    if pattern.len() > text.len() {
+        return None;
+    }
+
+    // prev[j]/cur[j]: best score with the current pattern character matched at text[j]
+    let mut prev = vec![NO_SCORE; text.len()];
+    let mut cur = vec![NO_SCORE; text.len()];
+
+    for (i, &pc) in pattern.iter().enumerate() {
+        // best score of the previous row ending at least two characters before j,
+        // already reduced by the gap penalty
+        let mut gap_best = NO_SCORE;
+        for (j, &tc) in text.iter().enumerate() {
+            if j >= 2 {
+                gap_best = gap_best.max(prev[j - 2]) - PENALTY_GAP;
+            }
+            cur[j] = if tc != pc {
+                NO_SCORE
+            } else if i == 0 {
+                SCORE_MATCH + bonus[j]
+            } else {
+                let consecutive = if j >= 1 {
+                    prev[j - 1] + BONUS_CONSECUTIVE
+                } else {
+                    NO_SCORE
+                };
+                let best = consecutive.max(gap_best);
+                if best <= NO_SCORE / 2 {
+                    NO_SCORE
+                } else {
+                    best + SCORE_MATCH + bonus[j]
+                }
+            };
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+
+    prev.into_iter().filter(|s| *s > NO_SCORE / 2).max()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::filter::fuzzy::FuzzyFactory;
+    use crate::filter::{FilterFactory, Match};
+    use crate::menu_item::MenuItem;
+
+    fn scores(items: &[&str], input: &str, case_insensitive: bool) -> Vec<Option<u32>> {
+        let items: Vec<MenuItem> = items
+            .iter()
+            .enumerate()
+            .map(|(index, i)| MenuItem::create(i, index))
+            .collect();
+        let mut factory = FuzzyFactory::create(&items, case_insensitive);
+        let mut filter = factory.create(input);
+        items
+            .iter()
+            .map(|i| match filter.match_item(i) {
+                Match::Score(s) => Some(s),
+                Match::NoMatch => None,
+                m => panic!("unexpected {:?}", m),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn subsequence_match() {
+        let s = scores(&["firefox", "thunderbird", "gimp"], "fx", false);
+        assert!(s[0].is_some());
+        assert_eq!(s[1], None);
+        assert_eq!(s[2], None);
+    }
+
+    #[test]
+    fn case_handling() {
+        assert_eq!(scores(&["FireFox"], "ff", false), vec![None]);
+        assert!(scores(&["FireFox"], "ff", true)[0].is_some());
+    }
+
+    #[test]
+    fn prefers_word_starts_and_runs() {
+        let s = scores(&["xfxoxo", "foo", "my-foo", "barFoo"], "foo", true);
+        assert!(s[1] > s[0]);
+        assert!(s[2] > s[0]);
+        assert!(s[3] > s[0]);
+
+        let s = scores(&["git-commit-amend", "gcxxxxxxa"], "gca", false);
+        assert!(s[0] > s[1]);
+    }
+}
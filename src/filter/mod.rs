@@ -1,9 +1,14 @@
-use std::fmt::Debug;
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
 
 use entry_filter::EntryFilter;
 
 use crate::filter::case_insensitive::CIFactory;
 use crate::filter::case_sensitive::CSFactory;
+use crate::filter::exact::ExactFactory;
+use crate::filter::fuzzy::FuzzyFactory;
+use crate::filter::pattern::RegexFactory;
+use crate::filter::words::WordsFactory;
 use crate::filter::Match::NoMatch;
 use crate::menu_item::MenuItem;
 use crate::tmenu_settings::TMenuSettings;
@@ -11,15 +16,56 @@ use crate::tmenu_settings::TMenuSettings;
 mod case_insensitive;
 mod case_sensitive;
 mod entry_filter;
+mod exact;
+mod fuzzy;
+mod pattern;
+mod words;
+
+/// How the input is matched against the items, see `--match`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MatchMode {
+    /// Items containing the input, or starting with it with `--match-prefix`, or fuzzy
+    /// matches with `--fuzzy`
+    Contains,
+    /// Items equal to the input
+    Exact,
+    /// Items containing all whitespace separated words of the input
+    Words,
+    /// Items matching the input as regular expression
+    Regex,
+}
+
+impl MatchMode {
+    pub fn parse(name: &str) -> Option<MatchMode> {
+        match name {
+            "contains" => Some(MatchMode::Contains),
+            "exact" => Some(MatchMode::Exact),
+            "words" => Some(MatchMode::Words),
+            "regex" => Some(MatchMode::Regex),
+            _ => None,
+        }
+    }
+}
 
 #[derive(PartialEq, Debug)]
 pub enum Match {
     NoMatch,
     Match,
     Index(u32),
+    /// Matched with a relevance score, higher is better
+    Score(u32),
 }
 
 impl Match {
+    /// Sort key for visible items, mnemonic and unscored matches rank above scored ones
+    pub fn rank(&self) -> u32 {
+        match self {
+            Match::NoMatch => 0,
+            Match::Score(score) => *score,
+            Match::Match | Match::Index(_) => u32::MAX,
+        }
+    }
+
     fn or_else<M: Into<Match>, F: FnOnce() -> M>(self, alternative: F) -> Match {
         if let Self::NoMatch = self {
             alternative().into()
@@ -29,18 +75,63 @@ impl Match {
     }
 }
 
-impl From<bool> for Match {
-    fn from(v: bool) -> Self {
-        if v {
-            Match::Match
+/// Score of an item value that equals the user input
+pub const SCORE_EXACT: u32 = 3;
+/// Score of an item value that starts with the user input
+pub const SCORE_PREFIX: u32 = 2;
+/// Score of an item value that contains the user input anywhere else
+pub const SCORE_SUBSTRING: u32 = 1;
+
+fn prefix_match(value: &str, input: &str) -> Match {
+    if value == input {
+        Match::Score(SCORE_EXACT)
+    } else if value.starts_with(input) {
+        Match::Score(SCORE_PREFIX)
+    } else {
+        Match::NoMatch
+    }
+}
+
+fn contains_match(value: &str, input: &str) -> Match {
+    prefix_match(value, input).or_else(|| {
+        if value.contains(input) {
+            Match::Score(SCORE_SUBSTRING)
         } else {
             Match::NoMatch
         }
+    })
+}
+
+type CreateFilterFactory = dyn Fn(&TMenuSettings) -> Box<dyn FilterFactory>;
+
+/// Creates the filter factory of a menu from its settings, instead of the built in filters
+#[derive(Clone)]
+pub struct CustomFilter(pub Rc<CreateFilterFactory>);
+
+impl Debug for CustomFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "CustomFilter")
     }
 }
 
 pub fn create_filter_factory(settings: &TMenuSettings) -> Box<dyn FilterFactory> {
-    if settings.case_insensitive {
+    if let Some(CustomFilter(create)) = &settings.custom_filter {
+        return create(settings);
+    }
+    let items = &settings.available_options;
+    let case_insensitive = settings.case_insensitive;
+    match settings.match_mode {
+        MatchMode::Exact => return Box::new(ExactFactory::create(items, case_insensitive)),
+        MatchMode::Words => return Box::new(WordsFactory::create(items, case_insensitive)),
+        MatchMode::Regex => return Box::new(RegexFactory::create(items, case_insensitive)),
+        MatchMode::Contains => {}
+    }
+    if settings.fuzzy {
+        Box::new(FuzzyFactory::create(
+            &settings.available_options,
+            settings.case_insensitive,
+        ))
+    } else if settings.case_insensitive {
         Box::new(CIFactory::create(
             &settings.available_options,
             settings.filter_by_prefix,
@@ -51,6 +142,10 @@ pub fn create_filter_factory(settings: &TMenuSettings) -> Box<dyn FilterFactory>
 }
 
 pub trait FilterFactory: Debug {
+    /// Makes items, that are appended to the menu after the factory was created,
+    /// available to filters
+    fn add_items(&mut self, items: &[MenuItem]);
+
     fn create<'b, 'a: 'b>(&'a mut self, input: &'b str) -> Box<dyn Filter<'b> + 'b> {
         if input.is_empty() {
             Box::new(MatchAllFilter)
@@ -60,6 +155,11 @@ pub trait FilterFactory: Debug {
     }
 
     fn create_filter<'b, 'a: 'b>(&'a mut self, input: &'b str) -> Box<dyn Filter<'b> + 'b>;
+
+    /// Why the input of the last created filter is invalid. All items match meanwhile.
+    fn input_error(&self) -> Option<&str> {
+        None
+    }
 }
 
 pub trait Filter<'a>: Debug {
@@ -109,3 +209,193 @@ fn match_mnemonic(mnemonic: &str, input: &str) -> Match {
         Match::NoMatch
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::filter::exact::ExactFactory;
+    use crate::filter::{
+        create_filter_factory, Match, MatchMode, SCORE_EXACT, SCORE_PREFIX, SCORE_SUBSTRING,
+    };
+    use crate::menu_item::MenuItem;
+    use crate::tmenu_settings::TMenuSettings;
+
+    fn matches(mut settings: TMenuSettings, items: &[&str], input: &str) -> Vec<Match> {
+        settings.available_options = items
+            .iter()
+            .enumerate()
+            .map(|(index, i)| MenuItem::create(i, index))
+            .collect();
+        let mut factory = create_filter_factory(&settings);
+        let mut filter = factory.create(input);
+        settings
+            .available_options
+            .iter()
+            .map(|i| filter.match_item(i))
+            .collect()
+    }
+
+    #[test]
+    fn scores_exact_prefix_substring() {
+        let items = ["xfoo", "foobar", "foo", "bar"];
+        let expected = vec![
+            Match::Score(SCORE_SUBSTRING),
+            Match::Score(SCORE_PREFIX),
+            Match::Score(SCORE_EXACT),
+            Match::NoMatch,
+        ];
+        assert_eq!(matches(TMenuSettings::default(), &items, "foo"), expected);
+
+        let case_insensitive = TMenuSettings {
+            case_insensitive: true,
+            ..TMenuSettings::default()
+        };
+        assert_eq!(matches(case_insensitive, &items, "FOO"), expected);
+    }
+
+    #[test]
+    fn prefix_filter_scores() {
+        let settings = TMenuSettings {
+            filter_by_prefix: true,
+            ..TMenuSettings::default()
+        };
+        assert_eq!(
+            matches(settings, &["xfoo", "foobar", "foo"], "foo"),
+            vec![
+                Match::NoMatch,
+                Match::Score(SCORE_PREFIX),
+                Match::Score(SCORE_EXACT)
+            ]
+        );
+    }
+
+    #[test]
+    fn mnemonic_ranks_first() {
+        let result = matches(TMenuSettings::default(), &["(f) bar", "f"], "f");
+        assert_eq!(result, vec![Match::Match, Match::Score(SCORE_EXACT)]);
+        assert!(result[0].rank() > result[1].rank());
+    }
+
+    #[test]
+    fn structured_items_match_their_text() {
+        let firefox =
+            MenuItem::from_fields(0, "Firefox", Some("firefox --new-window".into()), None);
+        let plain = MenuItem::create("firefox=Browser", 1);
+        for settings in [TMenuSettings::default(), with_mode(MatchMode::Words)] {
+            let fuzzy = TMenuSettings {
+                fuzzy: true,
+                ..settings.clone()
+            };
+            for settings in [settings, fuzzy] {
+                let mut factory = create_filter_factory(&settings);
+                factory.add_items(&[firefox.clone(), plain.clone()]);
+                let mut matches = |input: &str, item: &MenuItem| {
+                    factory.create(input).match_item(item) != Match::NoMatch
+                };
+                assert!(matches("Fire", &firefox));
+                assert!(!matches("new-window", &firefox));
+                // plain items still match their key
+                assert!(matches("fire", &plain));
+            }
+        }
+    }
+
+    fn with_mode(match_mode: MatchMode) -> TMenuSettings {
+        TMenuSettings {
+            match_mode,
+            ..TMenuSettings::default()
+        }
+    }
+
+    #[test]
+    fn exact_and_words_modes() {
+        let items = ["foo", "foobar", "Foo"];
+        assert_eq!(
+            matches(with_mode(MatchMode::Exact), &items, "foo"),
+            vec![Match::Score(SCORE_EXACT), Match::NoMatch, Match::NoMatch]
+        );
+        let case_insensitive = TMenuSettings {
+            case_insensitive: true,
+            ..with_mode(MatchMode::Exact)
+        };
+        assert_eq!(
+            matches(case_insensitive, &items, "FOO"),
+            vec![
+                Match::Score(SCORE_EXACT),
+                Match::NoMatch,
+                Match::Score(SCORE_EXACT)
+            ]
+        );
+
+        let items = ["git checkout main", "git commit", "cargo check"];
+        assert_eq!(
+            matches(with_mode(MatchMode::Words), &items, "check git"),
+            vec![
+                Match::Score(SCORE_SUBSTRING),
+                Match::NoMatch,
+                Match::NoMatch
+            ]
+        );
+        assert_eq!(
+            matches(with_mode(MatchMode::Words), &items, "git c"),
+            vec![
+                Match::Score(SCORE_PREFIX),
+                Match::Score(SCORE_PREFIX),
+                Match::NoMatch
+            ]
+        );
+    }
+
+    #[test]
+    fn regex_mode() {
+        let items = ["foo", "foobar", "barfoo", "bar"];
+        assert_eq!(
+            matches(with_mode(MatchMode::Regex), &items, "^foo"),
+            vec![
+                Match::Score(SCORE_EXACT),
+                Match::Score(SCORE_PREFIX),
+                Match::NoMatch,
+                Match::NoMatch
+            ]
+        );
+        assert_eq!(
+            matches(with_mode(MatchMode::Regex), &items, "o+$")[2],
+            Match::Score(SCORE_SUBSTRING)
+        );
+        // scored by the best match, not by the leftmost-first "f"
+        assert_eq!(
+            matches(with_mode(MatchMode::Regex), &items, "f|foo"),
+            vec![
+                Match::Score(SCORE_EXACT),
+                Match::Score(SCORE_PREFIX),
+                Match::Score(SCORE_SUBSTRING),
+                Match::NoMatch
+            ]
+        );
+
+        // invalid patterns match all items and report the error
+        let mut settings = with_mode(MatchMode::Regex);
+        settings.available_options = vec![MenuItem::create("foo", 0)];
+        let mut factory = create_filter_factory(&settings);
+        let mut filter = factory.create("(foo");
+        assert_eq!(
+            filter.match_item(&settings.available_options[0]),
+            Match::Match
+        );
+        drop(filter);
+        assert!(factory.input_error().is_some());
+        factory.create("foo");
+        assert_eq!(factory.input_error(), None);
+    }
+
+    #[test]
+    fn custom_filter() {
+        let settings = TMenuSettings::builder()
+            .match_mode(MatchMode::Regex)
+            .filter(|settings| Box::new(ExactFactory::create(&settings.available_options, false)))
+            .build();
+        assert_eq!(
+            matches(settings, &["fo.", "foo"], "fo."),
+            vec![Match::Score(SCORE_EXACT), Match::NoMatch]
+        );
+    }
+}
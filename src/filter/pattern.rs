@@ -0,0 +1,109 @@
+use regex::{Regex, RegexBuilder};
+
+use crate::filter::entry_filter::{Entry, EntryFilter};
+use crate::filter::{
+    Filter, FilterFactory, Match, MatchAllFilter, SCORE_EXACT, SCORE_PREFIX, SCORE_SUBSTRING,
+};
+use crate::menu_item::MenuItem;
+
+/// Matches items with the input as regular expression.
+///
+/// Items are scored by their best match: a match of the whole item ranks above one at its
+/// start, which ranks above a match anywhere else. While the input is no valid expression,
+/// all items match and the error is reported by [`FilterFactory::input_error`].
+#[derive(Debug)]
+pub struct RegexFactory {
+    entries: Vec<Entry>,
+    case_insensitive: bool,
+    error: Option<String>,
+}
+
+impl RegexFactory {
+    pub fn create(items: &[MenuItem], case_insensitive: bool) -> Self {
+        let mut factory = RegexFactory {
+            entries: Vec::with_capacity(items.len()),
+            case_insensitive,
+            error: None,
+        };
+        factory.add_items(items);
+        factory
+    }
+}
+
+impl FilterFactory for RegexFactory {
+    fn add_items(&mut self, items: &[MenuItem]) {
+        // the regex handles the case, the values are kept as they are
+        self.entries
+            .extend(items.iter().map(|i| Entry::create(i, false)));
+    }
+
+    fn create_filter<'b, 'a: 'b>(&'a mut self, input: &'b str) -> Box<dyn Filter<'b> + 'b> {
+        let build = |pattern: &str| {
+            RegexBuilder::new(pattern)
+                .case_insensitive(self.case_insensitive)
+                .build()
+        };
+        // anchored at the start and at both ends, to score the best match
+        let regexes = build(input).and_then(|regex| {
+            let prefix = build(&format!(r"\A(?:{})", input))?;
+            let whole = build(&format!(r"\A(?:{})\z", input))?;
+            Ok((regex, prefix, whole))
+        });
+        match regexes {
+            Ok((regex, prefix, whole)) => {
+                self.error = None;
+                Box::new(RegexFilter {
+                    regex,
+                    prefix,
+                    whole,
+                    input,
+                    acc: &self.entries,
+                })
+            }
+            Err(e) => {
+                // the last line names the problem, the lines before point at it
+                let message = e.to_string();
+                self.error = message.lines().last().map(str::to_string);
+                Box::new(MatchAllFilter)
+            }
+        }
+    }
+
+    fn input_error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+}
+
+#[derive(Debug)]
+pub struct RegexFilter<'a> {
+    regex: Regex,
+    /// The pattern anchored at the start of the item
+    prefix: Regex,
+    /// The pattern anchored at both ends of the item
+    whole: Regex,
+    input: &'a str,
+    acc: &'a [Entry],
+}
+
+impl<'a> EntryFilter for RegexFilter<'a> {
+    fn get_acc(&self) -> &[Entry] {
+        self.acc
+    }
+
+    fn get_input(&self) -> &str {
+        self.input
+    }
+
+    fn value_match(&self, entry: &Entry) -> Match {
+        // the leftmost match may be shorter than another one at the same position
+        if !self.regex.is_match(&entry.value) {
+            Match::NoMatch
+        } else if self.whole.is_match(&entry.value) {
+            Match::Score(SCORE_EXACT)
+        } else if self.prefix.is_match(&entry.value) {
+            Match::Score(SCORE_PREFIX)
+        } else {
+            Match::Score(SCORE_SUBSTRING)
+        }
+    }
+}
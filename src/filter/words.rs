@@ -0,0 +1,77 @@
+use crate::filter::entry_filter::{Entry, EntryFilter};
+use crate::filter::{contains_match, Filter, FilterFactory, Match, SCORE_SUBSTRING};
+use crate::menu_item::MenuItem;
+
+/// Matches items, that contain every whitespace separated word of the input, in any order
+#[derive(Debug)]
+pub struct WordsFactory {
+    entries: Vec<Entry>,
+    case_insensitive: bool,
+}
+
+impl WordsFactory {
+    pub fn create(items: &[MenuItem], case_insensitive: bool) -> Self {
+        let mut factory = WordsFactory {
+            entries: Vec::with_capacity(items.len()),
+            case_insensitive,
+        };
+        factory.add_items(items);
+        factory
+    }
+}
+
+impl FilterFactory for WordsFactory {
+    fn add_items(&mut self, items: &[MenuItem]) {
+        let case_insensitive = self.case_insensitive;
+        self.entries
+            .extend(items.iter().map(|i| Entry::create(i, case_insensitive)));
+    }
+
+    fn create_filter<'b, 'a: 'b>(&'a mut self, input: &'b str) -> Box<dyn Filter<'b> + 'b> {
+        let text = if self.case_insensitive {
+            input.to_lowercase()
+        } else {
+            input.to_string()
+        };
+        let words = text.split_whitespace().map(str::to_string).collect();
+        Box::new(WordsFilter {
+            text,
+            words,
+            input,
+            acc: &self.entries,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct WordsFilter<'a> {
+    /// The whole input, items containing it rank like in the default filter
+    text: String,
+    words: Vec<String>,
+    input: &'a str,
+    acc: &'a [Entry],
+}
+
+impl<'a> EntryFilter for WordsFilter<'a> {
+    fn get_acc(&self) -> &[Entry] {
+        self.acc
+    }
+
+    fn get_input(&self) -> &str {
+        self.input
+    }
+
+    fn value_match(&self, entry: &Entry) -> Match {
+        contains_match(&entry.value, &self.text).or_else(|| {
+            if self
+                .words
+                .iter()
+                .all(|word| entry.value.contains(word.as_str()))
+            {
+                Match::Score(SCORE_SUBSTRING)
+            } else {
+                Match::NoMatch
+            }
+        })
+    }
+}
@@ -0,0 +1,225 @@
+use serde::{Deserialize, Deserializer};
+
+/// A length in logical pixels, or relative to the size of the monitor
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Dimension {
+    Pixels(u32),
+    Percent(f64),
+}
+
+impl Dimension {
+    /// Parses `300` (logical pixels) or `50%`
+    pub fn parse(spec: &str) -> Result<Dimension, String> {
+        let invalid = || format!("Invalid size, expected pixels or percent: {}", spec);
+        match spec.trim().strip_suffix('%') {
+            Some(percent) => percent
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|p| (0.0..=100.0).contains(p))
+                .map(Dimension::Percent)
+                .ok_or_else(invalid),
+            None => spec
+                .trim()
+                .parse()
+                .map(Dimension::Pixels)
+                .map_err(|_| invalid()),
+        }
+    }
+
+    /// Physical pixels of the dimension, on a monitor `monitor` physical pixels wide or high
+    fn resolve(&self, monitor: u32, scale_factor: f64) -> u32 {
+        match self {
+            Dimension::Pixels(pixels) => (*pixels as f64 * scale_factor).round() as u32,
+            Dimension::Percent(percent) => (monitor as f64 * percent / 100.0).round() as u32,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Dimension {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Value {
+            Pixels(u32),
+            Text(String),
+        }
+        match Value::deserialize(deserializer)? {
+            Value::Pixels(pixels) => Ok(Dimension::Pixels(pixels)),
+            Value::Text(text) => Dimension::parse(&text).map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// Where the menu is placed on the monitor
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Placement {
+    Top,
+    Bottom,
+    Center,
+}
+
+/// Size and position of the menu window
+#[derive(Debug, Clone, PartialEq)]
+pub struct Geometry {
+    /// Defaults to the monitor width, or half of it, when centered
+    pub width: Option<Dimension>,
+    /// Defaults to the height of the content
+    pub height: Option<Dimension>,
+    pub max_width: Option<Dimension>,
+    /// Offset from the left edge, or from the centered position
+    pub x: Dimension,
+    /// Offset from the top or bottom edge, or from the centered position
+    pub y: Dimension,
+    pub placement: Placement,
+}
+
+impl Default for Geometry {
+    fn default() -> Self {
+        Geometry {
+            width: None,
+            height: None,
+            max_width: None,
+            x: Dimension::Pixels(0),
+            y: Dimension::Pixels(0),
+            placement: Placement::Top,
+        }
+    }
+}
+
+/// Position and size in physical pixels
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct WindowRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Geometry {
+    /// Window position and size on a monitor at `monitor.x`, `monitor.y` with the size
+    /// `monitor.width` x `monitor.height`, all in physical pixels.
+    ///
+    /// `content_height` is the logical height of the menu content.
+    pub fn resolve(
+        &self,
+        monitor: WindowRect,
+        scale_factor: f64,
+        content_height: u32,
+    ) -> WindowRect {
+        let default_width = match self.placement {
+            Placement::Center => Dimension::Percent(50.0),
+            _ => Dimension::Percent(100.0),
+        };
+        let mut width = self
+            .width
+            .unwrap_or(default_width)
+            .resolve(monitor.width, scale_factor);
+        if let Some(max_width) = self.max_width {
+            width = width.min(max_width.resolve(monitor.width, scale_factor));
+        }
+        let width = width.min(monitor.width);
+        let height = self
+            .height
+            .unwrap_or(Dimension::Pixels(content_height))
+            .resolve(monitor.height, scale_factor)
+            .min(monitor.height);
+
+        let x_offset = self.x.resolve(monitor.width, scale_factor) as i32;
+        let y_offset = self.y.resolve(monitor.height, scale_factor) as i32;
+        let free_width = (monitor.width - width) as i32;
+        let free_height = (monitor.height - height) as i32;
+        let (x, y) = match self.placement {
+            Placement::Top => (x_offset, y_offset),
+            Placement::Bottom => (x_offset, free_height - y_offset),
+            Placement::Center => (free_width / 2 + x_offset, free_height / 2 + y_offset),
+        };
+
+        WindowRect {
+            x: monitor.x + x,
+            y: monitor.y + y,
+            width,
+            height,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::geometry::{Dimension, Geometry, Placement, WindowRect};
+
+    const MONITOR: WindowRect = WindowRect {
+        x: 1920,
+        y: 0,
+        width: 2560,
+        height: 1440,
+    };
+
+    #[test]
+    fn parse_dimensions() {
+        assert_eq!(Dimension::parse("300").unwrap(), Dimension::Pixels(300));
+        assert_eq!(Dimension::parse("50%").unwrap(), Dimension::Percent(50.0));
+        assert_eq!(
+            Dimension::parse(" 12.5 %").unwrap(),
+            Dimension::Percent(12.5)
+        );
+        assert!(Dimension::parse("101%").is_err());
+        assert!(Dimension::parse("-3").is_err());
+        assert!(Dimension::parse("wide").is_err());
+    }
+
+    #[test]
+    fn default_bar() {
+        let rect = Geometry::default().resolve(MONITOR, 2.0, 30);
+        assert_eq!(
+            rect,
+            WindowRect {
+                x: 1920,
+                y: 0,
+                width: 2560,
+                height: 60
+            }
+        );
+    }
+
+    #[test]
+    fn bottom_with_offsets() {
+        let geometry = Geometry {
+            width: Some(Dimension::Percent(50.0)),
+            x: Dimension::Pixels(10),
+            y: Dimension::Percent(10.0),
+            placement: Placement::Bottom,
+            ..Geometry::default()
+        };
+        let rect = geometry.resolve(MONITOR, 1.5, 30);
+        assert_eq!(
+            rect,
+            WindowRect {
+                x: 1935,
+                y: 1440 - 45 - 144,
+                width: 1280,
+                height: 45
+            }
+        );
+    }
+
+    #[test]
+    fn centered_with_max_width() {
+        let geometry = Geometry {
+            height: Some(Dimension::Pixels(400)),
+            max_width: Some(Dimension::Pixels(800)),
+            placement: Placement::Center,
+            ..Geometry::default()
+        };
+        let rect = geometry.resolve(MONITOR, 1.0, 30);
+        assert_eq!(
+            rect,
+            WindowRect {
+                x: 1920 + 880,
+                y: 520,
+                width: 800,
+                height: 400
+            }
+        );
+    }
+}
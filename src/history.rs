@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::item_parser::{escape_tsv, unescape_tsv};
+
+/// Number of selections kept in a history file, older ones are dropped
+const MAX_RECORDS: usize = 1000;
+
+/// Number of records, above which the history file is compacted to [`MAX_RECORDS`]
+const COMPACT_AT: usize = MAX_RECORDS + MAX_RECORDS / 4;
+
+/// Length of the shortest record with a current time: ten digits, tab, value and newline
+const MIN_RECORD_LEN: u64 = 13;
+
+const DAY: u64 = 24 * 60 * 60;
+
+/// Selections made in previous runs of a menu.
+///
+/// The file stores one selection per line as `<unix seconds>\t<value>`, the oldest first.
+/// Tabs, line breaks and backslashes in values are escaped like in TSV input.
+#[derive(Debug, Default)]
+pub struct History {
+    records: Vec<Record>,
+}
+
+#[derive(Debug, Clone)]
+struct Record {
+    time: u64,
+    value: String,
+}
+
+impl History {
+    /// Reads the history file, a missing file is an empty history.
+    ///
+    /// Malformed lines, like one cut short by a crash, are skipped.
+    pub fn load(path: &Path) -> std::io::Result<History> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(History::default()),
+            Err(e) => return Err(e),
+        };
+
+        let mut records = Vec::new();
+        for line in BufReader::new(file).split(b'\n') {
+            if let Some(record) = String::from_utf8(line?).ok().and_then(|l| parse_record(&l)) {
+                records.push(record);
+            }
+        }
+
+        Ok(History { records })
+    }
+
+    /// Adds a selection to the history file.
+    ///
+    /// Menus sharing the history hold an exclusive lock on `<file>.lock`, while they append
+    /// their record and compact the file, so that they never lose each other's selections.
+    /// A last line left without newline by a crash is terminated before appending.
+    /// Once the file holds more than [`COMPACT_AT`] records, it is replaced by a file with
+    /// the newest [`MAX_RECORDS`], written to a temporary file first, so a crash never
+    /// leaves a truncated history.
+    pub fn append(path: &Path, value: &str, now: u64) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(with_suffix(path, ".lock"))?;
+        lock.lock()?;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)?;
+        let mut record = format!("{}\t{}\n", now, escape_tsv(value));
+        if !ends_with_newline(&mut file)? {
+            record.insert(0, '\n');
+        }
+        file.write_all(record.as_bytes())?;
+        let len = file.metadata()?.len();
+        drop(file);
+
+        // smaller files can't hold COMPACT_AT records, so they aren't read
+        if len > COMPACT_AT as u64 * MIN_RECORD_LEN {
+            let history = History::load(path)?;
+            if history.records.len() > COMPACT_AT {
+                history.compact(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces the history file with the newest [`MAX_RECORDS`] records.
+    ///
+    /// Only called with the lock held, so the temporary file isn't shared.
+    fn compact(&self, path: &Path) -> std::io::Result<()> {
+        let tmp_path = with_suffix(path, ".tmp");
+        let skip = self.records.len().saturating_sub(MAX_RECORDS);
+        let mut out = std::io::BufWriter::new(File::create(&tmp_path)?);
+        for record in &self.records[skip..] {
+            writeln!(out, "{}\t{}", record.time, escape_tsv(&record.value))?;
+        }
+        out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+
+        std::fs::rename(&tmp_path, path)
+    }
+
+    /// Frecency of all values in the history.
+    ///
+    /// Every selection adds a weight to its value, recent selections weigh more.
+    pub fn frecency(&self, now: u64) -> HashMap<&str, u32> {
+        let mut result = HashMap::new();
+        for record in &self.records {
+            *result.entry(record.value.as_str()).or_insert(0) +=
+                age_weight(now.saturating_sub(record.time));
+        }
+        result
+    }
+}
+
+fn age_weight(age: u64) -> u32 {
+    match age / DAY {
+        0..=3 => 100,
+        4..=13 => 70,
+        14..=30 => 50,
+        31..=90 => 30,
+        _ => 10,
+    }
+}
+
+fn parse_record(line: &str) -> Option<Record> {
+    let (time, value) = line.split_once('\t')?;
+    // escaped values have no tabs, this is a record glued to a line cut short
+    if value.contains('\t') {
+        return None;
+    }
+    Some(Record {
+        time: time.parse().ok()?,
+        value: unescape_tsv(value),
+    })
+}
+
+/// Whether the file is empty or ends with a complete line
+fn ends_with_newline(file: &mut File) -> std::io::Result<bool> {
+    if file.metadata()?.len() == 0 {
+        return Ok(true);
+    }
+    let mut last = [0];
+    file.seek(SeekFrom::End(-1))?;
+    file.read_exact(&mut last)?;
+    Ok(last[0] == b'\n')
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Current time in seconds since the unix epoch
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// History file of the menu with the given id: `$XDG_STATE_HOME/tmenu/<id>`,
+/// with `$XDG_STATE_HOME` defaulting to `~/.local/state`
+pub fn history_path_for_id(id: &str) -> Option<PathBuf> {
+    if id.is_empty() || id.contains(std::path::is_separator) || id.starts_with('.') {
+        return None;
+    }
+    let state_home = std::env::var_os("XDG_STATE_HOME")
+        .filter(|s| !s.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| Path::new(&h).join(".local/state")))?;
+
+    Some(state_home.join("tmenu").join(id))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::history::{History, COMPACT_AT, DAY, MAX_RECORDS};
+
+    #[test]
+    fn frecency_prefers_recent_and_frequent() {
+        let dir = std::env::temp_dir().join(format!("tmenu-history-{}", std::process::id()));
+        let path = dir.join("test");
+        let now = 1000 * DAY;
+
+        History::append(&path, "old", now - 100 * DAY).unwrap();
+        History::append(&path, "old", now - 100 * DAY).unwrap();
+        History::append(&path, "recent", now - DAY).unwrap();
+        History::append(&path, "frequent", now - 10 * DAY).unwrap();
+        History::append(&path, "frequent", now - 10 * DAY).unwrap();
+
+        let history = History::load(&path).unwrap();
+        let frecency = history.frecency(now);
+        assert_eq!(frecency.get("old"), Some(&20));
+        assert_eq!(frecency.get("recent"), Some(&100));
+        assert_eq!(frecency.get("frequent"), Some(&140));
+        assert_eq!(frecency.get("unknown"), None);
+
+        let full: String = (0..COMPACT_AT)
+            .map(|_| format!("{}\tother\n", now))
+            .collect();
+        std::fs::write(&path, full).unwrap();
+        History::append(&path, "recent", now - DAY).unwrap();
+        let history = History::load(&path).unwrap();
+        assert_eq!(history.records.len(), MAX_RECORDS);
+        assert_eq!(history.frecency(now).get("recent"), Some(&100));
+        assert_eq!(history.frecency(now).get("other"), Some(&99_900));
+        History::append(&path, "recent", now - DAY).unwrap();
+        assert_eq!(History::load(&path).unwrap().records.len(), MAX_RECORDS + 1);
+
+        // only the history and its lock file remain, no temporary files
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn concurrent_menus_keep_all_selections() {
+        let dir = std::env::temp_dir().join(format!("tmenu-shared-{}", std::process::id()));
+        let path = dir.join("test");
+        let menus: Vec<_> = (0..4)
+            .map(|menu| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..50 {
+                        History::append(&path, &format!("menu {}", menu), DAY).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for menu in menus {
+            menu.join().unwrap();
+        }
+
+        let history = History::load(&path).unwrap();
+        assert_eq!(history.records.len(), 200);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn partial_lines_and_escaped_values() {
+        let dir = std::env::temp_dir().join(format!("tmenu-partial-{}", std::process::id()));
+        let path = dir.join("test");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&path, "1\tok\nnot a record\n2\tfo").unwrap();
+
+        History::append(&path, "bar", DAY).unwrap();
+        History::append(&path, "two\nlines\tand \\n", DAY).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "1\tok\nnot a record\n2\tfo\n86400\tbar\n86400\ttwo\\nlines\\tand \\\\n\n"
+        );
+
+        let history = History::load(&path).unwrap();
+        let values: Vec<_> = history.records.iter().map(|r| r.value.as_str()).collect();
+        assert_eq!(values, ["ok", "fo", "bar", "two\nlines\tand \\n"]);
+
+        // a record glued to a partial line is skipped
+        std::fs::write(&path, "1\tfo2\tbar\n3\tbaz\n").unwrap();
+        assert_eq!(History::load(&path).unwrap().records.len(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn missing_file_is_empty() {
+        let history = History::load(std::path::Path::new("/nonexistent/tmenu/history")).unwrap();
+        assert!(history.records.is_empty());
+    }
+}
@@ -0,0 +1,170 @@
+use std::hash::Hash;
+
+use iced_core::{Length, Point, Rectangle, Size};
+use iced_native::layout::{self, Layout};
+use iced_native::{mouse, Hasher, Widget};
+use iced_wgpu::{Primitive, Renderer};
+
+use crate::layout::page_range;
+
+type Element<'a, Message> = iced_native::Element<'a, Message, Renderer>;
+type Defaults = <Renderer as iced_native::Renderer>::Defaults;
+
+/// Children before the items: the "<" and ">" indicators
+const INDICATORS: usize = 2;
+
+/// A row of items, that shows the page of items containing the active item.
+///
+/// The item widths are measured by the renderer during layout, so that the active item
+/// is always fully visible. "<" and ">" indicators are shown, when items precede or
+/// follow the page, also when they are not part of the bar, see [`bar_window`].
+///
+/// [`bar_window`]: crate::layout::bar_window
+pub struct ItemBar<'a, Message> {
+    active: Option<usize>,
+    /// Whether items precede or follow the items of the bar
+    more: (bool, bool),
+    /// The indicators, followed by the items, with a separator between two items
+    children: Vec<Element<'a, Message>>,
+}
+
+impl<'a, Message: 'a> ItemBar<'a, Message> {
+    pub fn new(
+        items: Vec<Element<'a, Message>>,
+        separator: impl Fn() -> Element<'a, Message>,
+        active: Option<usize>,
+        more: (bool, bool),
+        before: impl Into<Element<'a, Message>>,
+        after: impl Into<Element<'a, Message>>,
+    ) -> Self {
+        let mut children = Vec::with_capacity(INDICATORS + items.len() * 2);
+        children.push(before.into());
+        children.push(after.into());
+        for (position, item) in items.into_iter().enumerate() {
+            if position > 0 {
+                children.push(separator());
+            }
+            children.push(item);
+        }
+        ItemBar {
+            active,
+            more,
+            children,
+        }
+    }
+}
+
+impl<'a, Message> Widget<Message, Renderer> for ItemBar<'a, Message> {
+    fn width(&self) -> Length {
+        Length::Fill
+    }
+
+    fn height(&self) -> Length {
+        Length::Shrink
+    }
+
+    fn layout(&self, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        let limits = limits.width(Length::Fill);
+        let available = limits.max().width;
+        let loose = limits.loose();
+        let mut nodes: Vec<layout::Node> = self
+            .children
+            .iter()
+            .map(|child| child.layout(renderer, &loose))
+            .collect();
+
+        let height = nodes
+            .iter()
+            .map(|node| node.size().height)
+            .fold(0.0, f32::max);
+        let widths: Vec<f32> = nodes[INDICATORS..]
+            .iter()
+            .step_by(2)
+            .map(|node| node.size().width)
+            .collect();
+        let separator = nodes
+            .get(INDICATORS + 1)
+            .map(|node| node.size().width)
+            .unwrap_or(0.0);
+        let indicators = (nodes[0].size().width, nodes[1].size().width);
+        let page = page_range(
+            &widths,
+            separator,
+            available,
+            indicators,
+            self.more,
+            self.active.unwrap_or(0),
+        );
+
+        // items and separators of the page, the indicators are shown when needed
+        let mut shown = vec![false; nodes.len()];
+        shown[0] = page.start > 0 || self.more.0;
+        shown[1] = page.end < widths.len() || self.more.1;
+        for child in INDICATORS + 2 * page.start..INDICATORS + (2 * page.end).saturating_sub(1) {
+            shown[child] = true;
+        }
+
+        let mut x = 0.0;
+        for (child, node) in nodes.iter_mut().enumerate() {
+            if !shown[child] {
+                *node = layout::Node::new(Size::ZERO);
+                continue;
+            }
+            let size = node.size();
+            let y = (height - size.height) / 2.0;
+            if child == 1 {
+                node.move_to(Point::new(available - size.width, y));
+            } else {
+                node.move_to(Point::new(x, y));
+                x += size.width;
+            }
+        }
+
+        layout::Node::with_children(Size::new(available, height), nodes)
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut Renderer,
+        defaults: &Defaults,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        viewport: &Rectangle,
+    ) -> (Primitive, mouse::Interaction) {
+        let mut mouse_interaction = mouse::Interaction::default();
+        let primitives = self
+            .children
+            .iter()
+            .zip(layout.children())
+            // children outside of the page have no size
+            .filter(|(_, layout)| layout.bounds().size() != Size::ZERO)
+            .map(|(child, layout)| {
+                let (primitive, interaction) =
+                    child.draw(renderer, defaults, layout, cursor_position, viewport);
+                if interaction > mouse_interaction {
+                    mouse_interaction = interaction;
+                }
+                primitive
+            })
+            .collect();
+
+        (Primitive::Group { primitives }, mouse_interaction)
+    }
+
+    fn hash_layout(&self, state: &mut Hasher) {
+        struct Marker;
+        std::any::TypeId::of::<Marker>().hash(state);
+
+        self.active.hash(state);
+        self.more.hash(state);
+        for child in &self.children {
+            child.hash_layout(state);
+        }
+    }
+}
+
+impl<'a, Message: 'a> From<ItemBar<'a, Message>> for Element<'a, Message> {
+    fn from(bar: ItemBar<'a, Message>) -> Self {
+        Element::new(bar)
+    }
+}
@@ -0,0 +1,259 @@
+use serde::Deserialize;
+
+use crate::menu_item::MenuItem;
+
+/// Syntax of the menu items passed to tmenu
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum InputFormat {
+    /// `[(MNEMONIC)] [KEY=]VALUE`, see [`MenuItem::create`]
+    Plain,
+    /// One JSON object per line
+    Json,
+    /// Tab separated columns, mapped to item fields by a header line or a column spec
+    Tsv,
+    /// Each line is the text and the value of an item, like in dmenu
+    Verbatim,
+}
+
+impl InputFormat {
+    pub fn parse(name: &str) -> Option<InputFormat> {
+        match name {
+            "plain" => Some(InputFormat::Plain),
+            "json" => Some(InputFormat::Json),
+            "tsv" => Some(InputFormat::Tsv),
+            "verbatim" => Some(InputFormat::Verbatim),
+            _ => None,
+        }
+    }
+}
+
+/// Item field stored in a TSV column
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Column {
+    Text,
+    Value,
+    Mnemonic,
+    Icon,
+    Hint,
+    Ignore,
+}
+
+impl Column {
+    fn parse(name: &str) -> Option<Column> {
+        match name {
+            "text" => Some(Column::Text),
+            "value" => Some(Column::Value),
+            "mnemonic" => Some(Column::Mnemonic),
+            "icon" => Some(Column::Icon),
+            "hint" => Some(Column::Hint),
+            "_" => Some(Column::Ignore),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a comma separated column spec like `value,text,hint`.
+///
+/// `_` skips a column.
+pub fn parse_columns(spec: &str) -> Result<Vec<Column>, String> {
+    spec.split(',')
+        .map(|name| Column::parse(name.trim()).ok_or_else(|| format!("Unknown column: '{}'", name)))
+        .collect()
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ItemFields {
+    text: Option<String>,
+    value: Option<String>,
+    mnemonic: Option<String>,
+    icon: Option<String>,
+    hint: Option<String>,
+}
+
+impl ItemFields {
+    fn into_item(self, index: usize) -> Result<MenuItem, String> {
+        let text = self
+            .text
+            .as_deref()
+            .or(self.value.as_deref())
+            .ok_or("Item has neither text nor value")?;
+        let mut item = MenuItem::from_fields(
+            index,
+            text,
+            self.value.clone(),
+            self.mnemonic.filter(|m| !m.is_empty()),
+        );
+        item.icon = self.icon;
+        item.hint = self.hint;
+        Ok(item)
+    }
+}
+
+/// Creates menu items from input lines
+#[derive(Debug, Clone)]
+pub struct ItemParser {
+    format: InputFormat,
+    /// Columns of TSV input, taken from the first line, when no column spec is given
+    columns: Option<Vec<Column>>,
+}
+
+impl ItemParser {
+    pub fn new(format: InputFormat, columns: Option<Vec<Column>>) -> Self {
+        ItemParser { format, columns }
+    }
+
+    /// Parses a single input line to the item with the given index.
+    ///
+    /// Returns `None` for lines that do not define an item, like TSV header lines.
+    pub fn parse_line(&mut self, line: &str, index: usize) -> Result<Option<MenuItem>, String> {
+        match self.format {
+            InputFormat::Plain => Ok(Some(MenuItem::create(line, index))),
+            InputFormat::Verbatim => Ok(Some(MenuItem::from_fields(index, line, None, None))),
+            InputFormat::Json => {
+                if line.trim().is_empty() {
+                    return Ok(None);
+                }
+                let fields: ItemFields = serde_json::from_str(line).map_err(|e| e.to_string())?;
+                fields.into_item(index).map(Some)
+            }
+            InputFormat::Tsv => {
+                if let Some(columns) = &self.columns {
+                    parse_tsv(columns, line, index).map(Some)
+                } else {
+                    // unknown header names are skipped, so producers can add columns
+                    let header = line
+                        .split('\t')
+                        .map(|name| Column::parse(name.trim()).unwrap_or(Column::Ignore))
+                        .collect();
+                    self.columns = Some(header);
+                    Ok(None)
+                }
+            }
+        }
+    }
+}
+
+fn parse_tsv(columns: &[Column], line: &str, index: usize) -> Result<MenuItem, String> {
+    let mut fields = ItemFields::default();
+    for (column, field) in columns.iter().zip(line.split('\t')) {
+        let field = Some(unescape_tsv(field)).filter(|f| !f.is_empty());
+        match column {
+            Column::Text => fields.text = field,
+            Column::Value => fields.value = field,
+            Column::Mnemonic => fields.mnemonic = field,
+            Column::Icon => fields.icon = field,
+            Column::Hint => fields.hint = field,
+            Column::Ignore => {}
+        }
+    }
+    fields.into_item(index)
+}
+
+/// Escapes tabs, line breaks and backslashes like TSV writers, reverted by [`unescape_tsv`]
+pub(crate) fn escape_tsv(field: &str) -> String {
+    let mut result = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            '\t' => result.push_str("\\t"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\\' => result.push_str("\\\\"),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+/// Resolves the `\t`, `\n`, `\r` and `\\` escapes used by TSV writers
+pub(crate) fn unescape_tsv(field: &str) -> String {
+    let mut result = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('\\') => result.push('\\'),
+            Some(other) => {
+                result.push('\\');
+                result.push(other);
+            }
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use crate::item_parser::{parse_columns, Column, InputFormat, ItemParser};
+    use crate::menu_item::MenuItem;
+
+    fn parse(parser: &mut ItemParser, line: &str) -> MenuItem {
+        parser.parse_line(line, 7).unwrap().unwrap()
+    }
+
+    #[test]
+    fn parse_json() {
+        let mut parser = ItemParser::new(InputFormat::Json, None);
+        let item = parse(
+            &mut parser,
+            r#"{"text": "(a) b=c", "value": "=x", "mnemonic": "m", "hint": "h", "extra": 1}"#,
+        );
+        assert_eq!(item.text, "(m) (a) b=c");
+        assert_eq!(item.value(), "=x");
+        assert_eq!(item.mnemonic.as_deref(), Some("m"));
+        assert_eq!(item.hint.as_deref(), Some("h"));
+        assert_eq!(item.index, 7);
+
+        let item = parse(&mut parser, r#"{"value": "v", "icon": "i"}"#);
+        assert_eq!(item.text, "v");
+        assert_eq!(item.icon.as_deref(), Some("i"));
+
+        assert!(parser.parse_line("", 0).unwrap().is_none());
+        assert!(parser.parse_line("{}", 0).is_err());
+        assert!(parser.parse_line("text", 0).is_err());
+    }
+
+    #[test]
+    fn parse_verbatim() {
+        let mut parser = ItemParser::new(InputFormat::Verbatim, None);
+        let item = parse(&mut parser, "(a) b=c");
+        assert_eq!(item.text, "(a) b=c");
+        assert_eq!(item.value(), "(a) b=c");
+        assert_eq!(item.mnemonic, None);
+        assert_eq!(parse(&mut parser, "").text, "");
+    }
+
+    #[test]
+    fn parse_tsv_with_header() {
+        let mut parser = ItemParser::new(InputFormat::Tsv, None);
+        assert!(parser
+            .parse_line("value\tunknown\ttext", 0)
+            .unwrap()
+            .is_none());
+        let item = parse(&mut parser, "key\tx\tfirst\\tsecond");
+        assert_eq!(item.text, "first\tsecond");
+        assert_eq!(item.value(), "key");
+    }
+
+    #[test]
+    fn parse_tsv_with_columns() {
+        let columns = parse_columns("text,_,mnemonic").unwrap();
+        assert_eq!(
+            columns,
+            vec![Column::Text, Column::Ignore, Column::Mnemonic]
+        );
+        let mut parser = ItemParser::new(InputFormat::Tsv, Some(columns));
+        let item = parse(&mut parser, "(x) a=b\tignored");
+        assert_eq!(item.text, "(x) a=b");
+        assert_eq!(item.value, None);
+        assert_eq!(item.mnemonic, None);
+
+        assert!(parse_columns("text,label").is_err());
+    }
+}
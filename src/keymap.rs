@@ -0,0 +1,351 @@
+use std::path::Path;
+
+use iced_core::keyboard::{KeyCode, Modifiers};
+
+use crate::error::TMenuError;
+use crate::tmenu::MainAction;
+
+/// A key combination, that triggers a [`MainAction`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: Modifiers,
+}
+
+impl Key {
+    /// Parses key combinations like `ctrl+n`, `shift+tab` or `page_down`
+    pub fn parse(spec: &str) -> Result<Key, String> {
+        let mut parts: Vec<&str> = spec.split('+').map(str::trim).collect();
+        let name = parts.pop().filter(|n| !n.is_empty());
+        let code = name
+            .and_then(|n| parse_key_code(&n.to_lowercase()))
+            .ok_or_else(|| format!("Unknown key: '{}'", spec))?;
+
+        let mut modifiers = Modifiers::empty();
+        for modifier in parts {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => Modifiers::CTRL,
+                "shift" => Modifiers::SHIFT,
+                "alt" => Modifiers::ALT,
+                "logo" | "super" | "cmd" => Modifiers::LOGO,
+                _ => return Err(format!("Unknown modifier '{}' in '{}'", modifier, spec)),
+            };
+        }
+
+        Ok(Key { code, modifiers })
+    }
+}
+
+impl From<Key> for MainAction {
+    fn from(key: Key) -> Self {
+        MainAction::KeyPressed(key.code, key.modifiers)
+    }
+}
+
+/// Maps key combinations to the actions they trigger.
+///
+/// Bindings override the bindings of the preset, also when the preset is replaced later,
+/// so that `--keys` keeps the bindings of the config file.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    preset: Vec<(Key, MainAction)>,
+    bindings: Vec<(Key, MainAction)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::preset("default").unwrap()
+    }
+}
+
+impl Keymap {
+    /// Built in keymaps: `default`, `emacs` and `vim`.
+    ///
+    /// `emacs` and `vim` extend the default keymap with Ctrl key bindings.
+    pub fn preset(name: &str) -> Option<Keymap> {
+        Some(Keymap {
+            preset: preset_bindings(name)?,
+            bindings: Vec::new(),
+        })
+    }
+
+    /// Replaces the preset, the bindings stay
+    pub fn set_preset(&mut self, name: &str) -> Result<(), String> {
+        self.preset =
+            preset_bindings(name).ok_or_else(|| format!("Unknown keymap preset: {}", name))?;
+        Ok(())
+    }
+
+    /// Binds a key to an action, replacing the previous binding of the key
+    pub fn bind(&mut self, key: Key, action: MainAction) {
+        self.bindings.retain(|(k, _)| *k != key);
+        self.bindings.push((key, action));
+    }
+
+    /// Applies a binding like `ctrl+n=next`
+    pub fn bind_spec(&mut self, spec: &str) -> Result<(), String> {
+        let (key, action) = spec
+            .split_once('=')
+            .ok_or_else(|| format!("Expected KEY=ACTION, got '{}'", spec))?;
+        let action = parse_action(action.trim())
+            .ok_or_else(|| format!("Unknown action '{}' in '{}'", action.trim(), spec))?;
+        self.bind(Key::parse(key)?, action);
+        Ok(())
+    }
+
+    /// Applies all bindings of a keymap file.
+    ///
+    /// The file contains one `KEY = ACTION` binding per line, lines starting with `#`
+    /// are comments.
+    pub fn load(&mut self, path: &Path) -> Result<(), TMenuError> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            TMenuError::Io(format!("Failed reading keymap {}: {}", path.display(), e))
+        })?;
+        for (number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            self.bind_spec(line)
+                .map_err(|e| format!("{}:{}: {}", path.display(), number + 1, e))?;
+        }
+        Ok(())
+    }
+
+    /// Action bound to the pressed key
+    pub fn action(&self, code: KeyCode, modifiers: Modifiers) -> Option<MainAction> {
+        let pressed = Key { code, modifiers };
+        self.bindings
+            .iter()
+            .chain(&self.preset)
+            .find(|(key, _)| *key == pressed)
+            .map(|(_, action)| action.clone())
+    }
+}
+
+/// Bindings of the preset `name`, see [`Keymap::preset`]
+fn preset_bindings(name: &str) -> Option<Vec<(Key, MainAction)>> {
+    let extra: &[(&str, &str)] = match name {
+        "default" => &[],
+        "emacs" => &[
+            ("ctrl+n", "next"),
+            ("ctrl+p", "previous"),
+            ("ctrl+g", "abort"),
+            ("ctrl+j", "accept"),
+            ("ctrl+m", "accept"),
+            ("alt+v", "page-previous"),
+            ("ctrl+u", "clear-input"),
+            ("ctrl+w", "delete-word"),
+            ("alt+backspace", "delete-word"),
+        ],
+        "vim" => &[
+            ("ctrl+j", "next"),
+            ("ctrl+k", "previous"),
+            ("ctrl+n", "next"),
+            ("ctrl+p", "previous"),
+            ("ctrl+f", "page-next"),
+            ("ctrl+b", "page-previous"),
+            ("ctrl+[", "abort"),
+            ("ctrl+u", "clear-input"),
+            ("ctrl+w", "delete-word"),
+        ],
+        _ => return None,
+    };
+
+    // reversed, so that the extra bindings are found before the default bindings
+    let preset = DEFAULT_BINDINGS
+        .iter()
+        .chain(extra)
+        .rev()
+        .map(|(key, action)| (Key::parse(key).unwrap(), parse_action(action).unwrap()))
+        .collect();
+    Some(preset)
+}
+
+const DEFAULT_BINDINGS: &[(&str, &str)] = &[
+    ("escape", "abort"),
+    ("enter", "accept"),
+    ("numpad_enter", "accept"),
+    ("shift+enter", "accept-input"),
+    ("ctrl+enter", "accept-continue"),
+    ("tab", "next-complete"),
+    ("shift+tab", "previous-complete"),
+    ("right", "right"),
+    ("left", "left"),
+    ("down", "down"),
+    ("up", "up"),
+    ("ctrl+home", "first"),
+    ("ctrl+end", "last"),
+    ("page_down", "page-next"),
+    ("page_up", "page-previous"),
+    ("ctrl+space", "toggle-mark"),
+];
+
+/// Names of all actions, that can be bound to keys
+pub const ACTION_NAMES: &[&str] = &[
+    "none",
+    "abort",
+    "accept",
+    "accept-input",
+    "accept-continue",
+    "next",
+    "previous",
+    "next-complete",
+    "previous-complete",
+    "up",
+    "down",
+    "left",
+    "right",
+    "first",
+    "last",
+    "page-next",
+    "page-previous",
+    "clear-input",
+    "delete-word",
+    "toggle-mark",
+];
+
+pub fn parse_action(name: &str) -> Option<MainAction> {
+    use MainAction::*;
+    let action = match name {
+        "none" => Focus,
+        "abort" => Abort,
+        "accept" => Exit,
+        "accept-input" => AcceptInput,
+        "accept-continue" => AcceptContinue,
+        "next" => Next,
+        "previous" => Previous,
+        "next-complete" => NextTab,
+        "previous-complete" => PreviousTab,
+        "up" => Up,
+        "down" => Down,
+        "left" => Left,
+        "right" => Right,
+        "first" => First,
+        "last" => Last,
+        "page-next" => PageNext,
+        "page-previous" => PagePrevious,
+        "clear-input" => ClearInput,
+        "delete-word" => DeleteWord,
+        "toggle-mark" => ToggleMark,
+        _ => return None,
+    };
+    Some(action)
+}
+
+pub fn parse_key_code(name: &str) -> Option<KeyCode> {
+    use KeyCode::*;
+    const LETTERS: [KeyCode; 26] = [
+        A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    ];
+    const DIGITS: [KeyCode; 10] = [Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9];
+    const FUNCTION_KEYS: [KeyCode; 12] = [F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12];
+
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if c.is_ascii_lowercase() {
+            return Some(LETTERS[(c as u8 - b'a') as usize]);
+        }
+        if c.is_ascii_digit() {
+            return Some(DIGITS[(c as u8 - b'0') as usize]);
+        }
+    }
+    if let Some(number) = name.strip_prefix('f').and_then(|n| n.parse::<usize>().ok()) {
+        return FUNCTION_KEYS.get(number.wrapping_sub(1)).copied();
+    }
+
+    let code = match name {
+        "escape" | "esc" => Escape,
+        "enter" | "return" => Enter,
+        "numpad_enter" => NumpadEnter,
+        "tab" => Tab,
+        "space" => Space,
+        "backspace" => Backspace,
+        "delete" => Delete,
+        "insert" => Insert,
+        "home" => Home,
+        "end" => End,
+        "page_up" | "pageup" => PageUp,
+        "page_down" | "pagedown" => PageDown,
+        "up" => Up,
+        "down" => Down,
+        "left" => Left,
+        "right" => Right,
+        "[" => LBracket,
+        "]" => RBracket,
+        "," | "comma" => Comma,
+        "." | "period" => Period,
+        "-" | "minus" => Minus,
+        "/" | "slash" => Slash,
+        ";" | "semicolon" => Semicolon,
+        _ => return None,
+    };
+    Some(code)
+}
+
+#[cfg(test)]
+mod test {
+    use iced_core::keyboard::{KeyCode, Modifiers};
+
+    use crate::keymap::{parse_action, Key, Keymap, ACTION_NAMES};
+    use crate::tmenu::MainAction;
+
+    #[test]
+    fn parse_keys() {
+        let key = Key::parse("ctrl+shift+n").unwrap();
+        assert_eq!(key.code, KeyCode::N);
+        assert_eq!(key.modifiers, Modifiers::CTRL | Modifiers::SHIFT);
+        assert_eq!(Key::parse("F12").unwrap().code, KeyCode::F12);
+        assert_eq!(Key::parse("ctrl+[").unwrap().code, KeyCode::LBracket);
+        assert_eq!(Key::parse("7").unwrap().code, KeyCode::Key7);
+        assert!(Key::parse("f13").is_err());
+        assert!(Key::parse("hyper+a").is_err());
+        assert!(Key::parse("ctrl+").is_err());
+    }
+
+    #[test]
+    fn bind_and_lookup() {
+        let mut keymap = Keymap::default();
+        assert!(matches!(
+            keymap.action(KeyCode::Escape, Modifiers::empty()),
+            Some(MainAction::Abort)
+        ));
+        assert!(keymap.action(KeyCode::N, Modifiers::CTRL).is_none());
+
+        keymap.bind_spec("escape = clear-input").unwrap();
+        keymap.bind_spec("ctrl+n=next").unwrap();
+        assert!(matches!(
+            keymap.action(KeyCode::Escape, Modifiers::empty()),
+            Some(MainAction::ClearInput)
+        ));
+        assert!(matches!(
+            keymap.action(KeyCode::N, Modifiers::CTRL),
+            Some(MainAction::Next)
+        ));
+        assert!(keymap.bind_spec("ctrl+n").is_err());
+        assert!(keymap.bind_spec("ctrl+n=jump").is_err());
+    }
+
+    #[test]
+    fn presets() {
+        let emacs = Keymap::preset("emacs").unwrap();
+        assert!(matches!(
+            emacs.action(KeyCode::P, Modifiers::CTRL),
+            Some(MainAction::Previous)
+        ));
+        assert!(matches!(
+            emacs.action(KeyCode::Tab, Modifiers::SHIFT),
+            Some(MainAction::PreviousTab)
+        ));
+        let vim = Keymap::preset("vim").unwrap();
+        assert!(matches!(
+            vim.action(KeyCode::K, Modifiers::CTRL),
+            Some(MainAction::Previous)
+        ));
+        assert!(Keymap::preset("nano").is_none());
+
+        for name in ACTION_NAMES {
+            assert!(parse_action(name).is_some(), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,596 @@
+//! Shows the menu on a wlr-layer-shell overlay surface.
+//!
+//! Wayland compositors ignore the position and stacking requests of regular windows.
+//! The layer shell protocol of wlroots based compositors places the surface at a screen
+//! edge above all windows, and grants it the keyboard focus exclusively. winit does not
+//! support the protocol, so this module drives the iced program itself: it renders with
+//! the wgpu compositor and feeds the keyboard events of the seat to the program.
+
+use std::cell::{Cell, RefCell};
+use std::ffi::c_void;
+use std::rc::Rc;
+use std::time::Duration;
+
+use iced_core::keyboard::{self, KeyCode, Modifiers};
+use iced_core::{Point, Size};
+use iced_futures::futures::channel::mpsc;
+use iced_futures::Executor;
+use iced_graphics::window::{Compositor as _, SurfaceError};
+use iced_graphics::Viewport;
+use iced_native::program::State;
+use iced_native::{clipboard, event, Debug, Event};
+use iced_winit::Application;
+use raw_window_handle::unix::WaylandHandle;
+use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+use smithay_client_toolkit::environment::{Environment, SimpleGlobal};
+use smithay_client_toolkit::output::{with_output_info, OutputInfo};
+use smithay_client_toolkit::reexports::calloop::EventLoop;
+use smithay_client_toolkit::reexports::client::protocol::wl_output::WlOutput;
+use smithay_client_toolkit::reexports::client::{Attached, Display, EventQueue};
+use smithay_client_toolkit::reexports::protocols::wlr::unstable::layer_shell::v1::client::{
+    zwlr_layer_shell_v1::{Layer, ZwlrLayerShellV1},
+    zwlr_layer_surface_v1::{self, Anchor, KeyboardInteractivity},
+};
+use smithay_client_toolkit::seat::keyboard::{
+    keysyms, map_keyboard_repeat, Event as KeyboardEvent, KeyState, ModifiersState, RepeatKind,
+};
+use smithay_client_toolkit::seat::with_seat_data;
+use smithay_client_toolkit::{default_environment, new_default_environment, WaylandSource};
+
+use crate::app::TMenuApp;
+use crate::geometry::{Geometry, Placement, WindowRect};
+use crate::keymap::parse_key_code;
+use crate::monitor::{select_monitor, MonitorInfo};
+use crate::tmenu::{ExitState, MainAction};
+use crate::tmenu_settings::TMenuSettings;
+
+/// Longest wait for Wayland events, before the messages of the subscriptions are handled
+const FRAME_TIME: Duration = Duration::from_millis(16);
+
+default_environment!(LayerEnv,
+    fields = [
+        layer_shell: SimpleGlobal<ZwlrLayerShellV1>,
+    ],
+    singles = [
+        ZwlrLayerShellV1 => layer_shell
+    ],
+);
+
+/// A connection to a Wayland compositor, that supports the layer shell
+pub struct LayerShell {
+    env: Environment<LayerEnv>,
+    display: Display,
+    queue: EventQueue,
+    layer_shell: Attached<ZwlrLayerShellV1>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum SurfaceEvent {
+    Configure { width: u32, height: u32 },
+    Closed,
+}
+
+/// Anchor, margins and size of the layer surface, in logical pixels
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct SurfacePlacement {
+    anchor_bottom: bool,
+    /// Distance of the surface from the anchored edges: top or bottom, and left
+    margin: (i32, i32),
+    width: u32,
+    height: u32,
+}
+
+/// The raw handles of the layer surface, for creating the wgpu surface
+struct SurfaceHandle {
+    display: *mut c_void,
+    surface: *mut c_void,
+}
+
+unsafe impl HasRawWindowHandle for SurfaceHandle {
+    fn raw_window_handle(&self) -> RawWindowHandle {
+        RawWindowHandle::Wayland(WaylandHandle {
+            surface: self.surface,
+            display: self.display,
+            ..WaylandHandle::empty()
+        })
+    }
+}
+
+type Runtime = iced_futures::Runtime<
+    iced_native::Hasher,
+    (Event, event::Status),
+    iced_futures::executor::ThreadPool,
+    mpsc::UnboundedSender<MainAction>,
+    MainAction,
+>;
+
+/// Connects to the compositor of `WAYLAND_DISPLAY`.
+///
+/// Fails, when there is no Wayland compositor, or it does not support the layer shell.
+pub fn connect() -> Result<LayerShell, String> {
+    let (env, display, queue) =
+        new_default_environment!(LayerEnv, fields = [layer_shell: SimpleGlobal::new(),])
+            .map_err(|e| format!("Failed to connect to the Wayland compositor: {}", e))?;
+    let layer_shell = env
+        .get_global::<ZwlrLayerShellV1>()
+        .ok_or("The Wayland compositor does not support the layer shell")?;
+    Ok(LayerShell {
+        env,
+        display,
+        queue,
+        layer_shell,
+    })
+}
+
+impl LayerShell {
+    /// Shows the menu until it exits, or the compositor closes the surface
+    pub fn run(
+        self,
+        settings: TMenuSettings,
+        renderer_settings: iced_wgpu::Settings,
+    ) -> Result<(), String> {
+        let LayerShell {
+            env,
+            display,
+            queue,
+            layer_shell,
+        } = self;
+        let exit_state = settings.exit_state.clone();
+
+        let outputs = env.get_all_outputs();
+        let monitors: Vec<MonitorInfo> = outputs
+            .iter()
+            .filter_map(|output| with_output_info(output, output_monitor).flatten())
+            .collect();
+        if settings.verbose {
+            eprintln!("Outputs:");
+            for (index, info) in monitors.iter().enumerate() {
+                eprintln!("  {}: {}", index, info);
+            }
+        }
+        // without a selection, the compositor chooses the output, usually the focused one
+        let selected = select_monitor(&settings.monitor, &monitors);
+        let output: Option<&WlOutput> = selected.and_then(|index| outputs.get(index));
+        let monitor = selected.or(Some(0)).and_then(|index| monitors.get(index));
+        let placement = monitor.map(|monitor| {
+            surface_placement(&settings.geometry, monitor, settings.content_height())
+        });
+        let scale_factor = monitor.map(|m| m.scale_factor).unwrap_or(1.0);
+
+        let surface = env.create_surface().detach();
+        let layer_surface =
+            layer_shell.get_layer_surface(&surface, output, Layer::Overlay, "tmenu".to_string());
+        layer_surface.set_keyboard_interactivity(KeyboardInteractivity::Exclusive);
+        let (width, height) = match placement {
+            Some(placement) => {
+                let (edge, left) = placement.margin;
+                if placement.anchor_bottom {
+                    layer_surface.set_anchor(Anchor::Bottom | Anchor::Left);
+                    layer_surface.set_margin(0, 0, edge, left);
+                } else {
+                    layer_surface.set_anchor(Anchor::Top | Anchor::Left);
+                    layer_surface.set_margin(edge, 0, 0, left);
+                }
+                (placement.width, placement.height)
+            }
+            None => {
+                // no output information, span the whole edge
+                let edge = match settings.geometry.placement {
+                    Placement::Bottom => Anchor::Bottom,
+                    _ => Anchor::Top,
+                };
+                layer_surface.set_anchor(edge | Anchor::Left | Anchor::Right);
+                (0, settings.content_height())
+            }
+        };
+        layer_surface.set_size(width, height);
+        surface.set_buffer_scale(scale_factor.round() as i32);
+
+        let surface_event = Rc::new(Cell::new(None::<SurfaceEvent>));
+        let surface_event_handle = Rc::clone(&surface_event);
+        layer_surface.quick_assign(move |layer_surface, event, _| match event {
+            zwlr_layer_surface_v1::Event::Configure {
+                serial,
+                width,
+                height,
+            } => {
+                layer_surface.ack_configure(serial);
+                if surface_event_handle.get() != Some(SurfaceEvent::Closed) {
+                    surface_event_handle.set(Some(SurfaceEvent::Configure { width, height }));
+                }
+            }
+            zwlr_layer_surface_v1::Event::Closed => {
+                surface_event_handle.set(Some(SurfaceEvent::Closed))
+            }
+            _ => {}
+        });
+        surface.commit();
+
+        let mut event_loop = EventLoop::<()>::try_new()
+            .map_err(|e| format!("Failed to create the event loop: {}", e))?;
+        WaylandSource::new(queue)
+            .quick_insert(event_loop.handle())
+            .map_err(|e| format!("Failed to watch the Wayland connection: {}", e.error))?;
+
+        let events = Rc::new(RefCell::new(Vec::<Event>::new()));
+        let mut keyboards = Vec::new();
+        for seat in env.get_all_seats() {
+            if !with_seat_data(&seat, |seat| seat.has_keyboard && !seat.defunct).unwrap_or(false) {
+                continue;
+            }
+            let events = Rc::clone(&events);
+            let mut modifiers = Modifiers::empty();
+            let keyboard = map_keyboard_repeat(
+                event_loop.handle(),
+                &seat,
+                None,
+                RepeatKind::System,
+                move |event, _, _| match event {
+                    KeyboardEvent::Key {
+                        keysym,
+                        state,
+                        utf8,
+                        ..
+                    } => events.borrow_mut().extend(keyboard_events(
+                        keysym,
+                        utf8.as_deref(),
+                        state == KeyState::Pressed,
+                        modifiers,
+                    )),
+                    KeyboardEvent::Repeat { keysym, utf8, .. } => events
+                        .borrow_mut()
+                        .extend(keyboard_events(keysym, utf8.as_deref(), true, modifiers)),
+                    KeyboardEvent::Modifiers { modifiers: state } => {
+                        modifiers = convert_modifiers(state);
+                        events.borrow_mut().push(Event::Keyboard(
+                            keyboard::Event::ModifiersChanged(modifiers),
+                        ));
+                    }
+                    _ => {}
+                },
+            )
+            .map_err(|e| format!("Failed to map the keyboard: {:?}", e))?;
+            keyboards.push(keyboard);
+        }
+        if keyboards.is_empty() {
+            eprintln!("No keyboard found");
+        }
+
+        let handle = SurfaceHandle {
+            display: display.get_display_ptr() as *mut c_void,
+            surface: surface.as_ref().c_ptr() as *mut c_void,
+        };
+        let (mut compositor, mut renderer) =
+            iced_wgpu::window::Compositor::new(renderer_settings, Some(&handle))
+                .map_err(|e| format!("Failed to create the renderer: {}", e))?;
+        let mut wgpu_surface = compositor.create_surface(&handle);
+        let mut viewport = Viewport::with_physical_size(
+            physical_size(width.max(1), height, scale_factor),
+            scale_factor,
+        );
+
+        let (sender, mut receiver) = mpsc::unbounded();
+        let executor = iced_futures::executor::ThreadPool::new()
+            .map_err(|e| format!("Failed to create the executor: {}", e))?;
+        let mut runtime = Runtime::new(executor, sender);
+        let mut debug = Debug::new();
+        // the menu does not return commands
+        let (program, _) = runtime.enter(|| TMenuApp::new(settings));
+        runtime.track(program.subscription());
+        let cursor_position = Point::new(-1.0, -1.0);
+        let mut state = State::new(
+            program,
+            viewport.logical_size(),
+            cursor_position,
+            &mut renderer,
+            &mut debug,
+        );
+
+        let mut configured = false;
+        let mut redraw = false;
+        loop {
+            event_loop
+                .dispatch(Some(FRAME_TIME), &mut ())
+                .map_err(|e| format!("Wayland connection failed: {}", e))?;
+
+            match surface_event.take() {
+                Some(SurfaceEvent::Configure { width, height }) => {
+                    let size = physical_size(width, height, scale_factor);
+                    compositor.configure_surface(&mut wgpu_surface, size.width, size.height);
+                    viewport = Viewport::with_physical_size(size, scale_factor);
+                    configured = true;
+                    redraw = true;
+                }
+                Some(SurfaceEvent::Closed) => {
+                    exit_state.replace(ExitState::Abort);
+                    break;
+                }
+                None => {}
+            }
+
+            for event in events.borrow_mut().drain(..) {
+                runtime.broadcast((event.clone(), event::Status::Ignored));
+                state.queue_event(event);
+            }
+            while let Ok(Some(message)) = receiver.try_next() {
+                state.queue_message(message);
+            }
+            if !state.is_queue_empty() {
+                let _ = runtime.enter(|| {
+                    state.update(
+                        viewport.logical_size(),
+                        cursor_position,
+                        &mut renderer,
+                        &mut clipboard::Null,
+                        &mut debug,
+                    )
+                });
+                runtime.track(state.program().subscription());
+                redraw = true;
+            }
+            if state.program().should_exit() {
+                break;
+            }
+
+            if configured && redraw {
+                match compositor.draw(
+                    &mut renderer,
+                    &mut wgpu_surface,
+                    &viewport,
+                    state.program().background_color(),
+                    state.primitive(),
+                    &debug.overlay(),
+                ) {
+                    Ok(_) => redraw = false,
+                    Err(SurfaceError::OutOfMemory) => {
+                        return Err("Out of memory while rendering the menu".to_string())
+                    }
+                    // drawn again in the next iteration
+                    Err(_) => {}
+                }
+            }
+        }
+
+        layer_surface.destroy();
+        surface.destroy();
+        display
+            .flush()
+            .map_err(|e| format!("Wayland connection failed: {}", e))?;
+        Ok(())
+    }
+}
+
+fn physical_size(width: u32, height: u32, scale_factor: f64) -> Size<u32> {
+    Size::new(
+        (width as f64 * scale_factor).round() as u32,
+        (height as f64 * scale_factor).round() as u32,
+    )
+}
+
+/// The monitor of an output in its current mode.
+///
+/// Outputs are named by their connector, like `DP-1`, so that `--monitor` selects them
+/// by the names of the compositor. Compositors before wl_output version 4 send no name.
+fn output_monitor(info: &OutputInfo) -> Option<MonitorInfo> {
+    let mode = info.modes.iter().find(|mode| mode.is_current)?;
+    Some(MonitorInfo {
+        name: Some(info.name.clone()).filter(|name| !name.is_empty()),
+        rect: WindowRect {
+            x: info.location.0,
+            y: info.location.1,
+            width: mode.dimensions.0 as u32,
+            height: mode.dimensions.1 as u32,
+        },
+        scale_factor: info.scale_factor as f64,
+    })
+}
+
+/// Places the menu on `monitor`, relative to the top or bottom left corner of the output
+fn surface_placement(
+    geometry: &Geometry,
+    monitor: &MonitorInfo,
+    content_height: u32,
+) -> SurfacePlacement {
+    let output = WindowRect {
+        x: 0,
+        y: 0,
+        ..monitor.rect
+    };
+    let rect = geometry.resolve(output, monitor.scale_factor, content_height);
+    let logical = |pixels: i64| (pixels as f64 / monitor.scale_factor).round() as i32;
+    let anchor_bottom = geometry.placement == Placement::Bottom;
+    let edge = if anchor_bottom {
+        output.height as i64 - rect.y as i64 - rect.height as i64
+    } else {
+        rect.y as i64
+    };
+    SurfacePlacement {
+        anchor_bottom,
+        margin: (logical(edge), logical(rect.x as i64)),
+        width: logical(rect.width as i64) as u32,
+        height: logical(rect.height as i64) as u32,
+    }
+}
+
+fn convert_modifiers(state: ModifiersState) -> Modifiers {
+    let mut modifiers = Modifiers::empty();
+    modifiers.set(Modifiers::SHIFT, state.shift);
+    modifiers.set(Modifiers::CTRL, state.ctrl);
+    modifiers.set(Modifiers::ALT, state.alt);
+    modifiers.set(Modifiers::LOGO, state.logo);
+    modifiers
+}
+
+/// The iced events of a key press or release, like winit reports them: the key, followed
+/// by the typed characters
+fn keyboard_events(
+    keysym: u32,
+    utf8: Option<&str>,
+    pressed: bool,
+    modifiers: Modifiers,
+) -> Vec<Event> {
+    let mut events = Vec::new();
+    if let Some(key_code) = key_code(keysym) {
+        events.push(Event::Keyboard(if pressed {
+            keyboard::Event::KeyPressed {
+                key_code,
+                modifiers,
+            }
+        } else {
+            keyboard::Event::KeyReleased {
+                key_code,
+                modifiers,
+            }
+        }));
+    }
+    if pressed {
+        let typed = utf8.unwrap_or_default().chars().filter(|c| !c.is_control());
+        events.extend(typed.map(|c| Event::Keyboard(keyboard::Event::CharacterReceived(c))));
+    }
+    events
+}
+
+/// The key code of an xkb keysym
+fn key_code(keysym: u32) -> Option<KeyCode> {
+    let name = match keysym {
+        keysyms::XKB_KEY_Escape => "escape",
+        keysyms::XKB_KEY_Return => "enter",
+        keysyms::XKB_KEY_KP_Enter => "numpad_enter",
+        keysyms::XKB_KEY_Tab | keysyms::XKB_KEY_ISO_Left_Tab => "tab",
+        keysyms::XKB_KEY_BackSpace => "backspace",
+        keysyms::XKB_KEY_Delete => "delete",
+        keysyms::XKB_KEY_Insert => "insert",
+        keysyms::XKB_KEY_Home => "home",
+        keysyms::XKB_KEY_End => "end",
+        keysyms::XKB_KEY_Prior => "page_up",
+        keysyms::XKB_KEY_Next => "page_down",
+        keysyms::XKB_KEY_Up => "up",
+        keysyms::XKB_KEY_Down => "down",
+        keysyms::XKB_KEY_Left => "left",
+        keysyms::XKB_KEY_Right => "right",
+        keysyms::XKB_KEY_space => "space",
+        keysyms::XKB_KEY_F1..=keysyms::XKB_KEY_F12 => {
+            return parse_key_code(&format!("f{}", keysym - keysyms::XKB_KEY_F1 + 1))
+        }
+        // the keysyms of printable ASCII characters are their code points
+        keysym => {
+            let c = char::from_u32(keysym).filter(char::is_ascii_graphic)?;
+            return parse_key_code(&c.to_ascii_lowercase().to_string());
+        }
+    };
+    parse_key_code(name)
+}
+
+#[cfg(test)]
+mod test {
+    use iced_core::keyboard::{self, KeyCode, Modifiers};
+    use iced_native::Event;
+    use smithay_client_toolkit::output::{Mode, OutputInfo};
+    use smithay_client_toolkit::reexports::client::protocol::wl_output::{Subpixel, Transform};
+    use smithay_client_toolkit::seat::keyboard::keysyms;
+
+    use crate::geometry::{Dimension, Geometry, Placement, WindowRect};
+    use crate::layer_shell::{
+        key_code, keyboard_events, output_monitor, surface_placement, SurfacePlacement,
+    };
+    use crate::monitor::{select_monitor, MonitorInfo, MonitorSelection};
+
+    #[test]
+    fn translate_keysyms() {
+        assert_eq!(key_code(keysyms::XKB_KEY_Escape), Some(KeyCode::Escape));
+        assert_eq!(key_code(keysyms::XKB_KEY_ISO_Left_Tab), Some(KeyCode::Tab));
+        assert_eq!(key_code(keysyms::XKB_KEY_F12), Some(KeyCode::F12));
+        assert_eq!(key_code(keysyms::XKB_KEY_N), Some(KeyCode::N));
+        assert_eq!(
+            key_code(keysyms::XKB_KEY_bracketleft),
+            Some(KeyCode::LBracket)
+        );
+        assert_eq!(key_code(keysyms::XKB_KEY_adiaeresis), None);
+
+        let events = keyboard_events(keysyms::XKB_KEY_a, Some("a"), true, Modifiers::empty());
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            events[1],
+            Event::Keyboard(keyboard::Event::CharacterReceived('a'))
+        ));
+        let events = keyboard_events(keysyms::XKB_KEY_Return, Some("\r"), true, Modifiers::CTRL);
+        assert_eq!(events.len(), 1);
+    }
+
+    #[test]
+    fn select_outputs_by_name() {
+        let output = |id, name: &str, x| OutputInfo {
+            id,
+            model: "U2720Q".to_string(),
+            make: "Dell".to_string(),
+            name: name.to_string(),
+            description: format!("Dell U2720Q ({})", name),
+            location: (x, 0),
+            physical_size: (600, 340),
+            subpixel: Subpixel::Unknown,
+            transform: Transform::Normal,
+            scale_factor: 1,
+            modes: vec![Mode {
+                dimensions: (2560, 1440),
+                refresh_rate: 60000,
+                is_current: true,
+                is_preferred: true,
+            }],
+            obsolete: false,
+        };
+        let monitors: Vec<MonitorInfo> = [output(1, "DP-1", 0), output(2, "DP-2", 2560)]
+            .iter()
+            .filter_map(output_monitor)
+            .collect();
+        assert_eq!(monitors.len(), 2);
+        assert_eq!(monitors[1].rect.x, 2560);
+        let select = |spec| select_monitor(&MonitorSelection::parse(spec), &monitors);
+        assert_eq!(select("DP-2"), Some(1));
+        assert_eq!(select("DP-1"), Some(0));
+        assert_eq!(select("HDMI-A-1"), None);
+
+        // outputs without a current mode cannot show the menu
+        let mut unnamed = output(3, "", 0);
+        assert_eq!(output_monitor(&unnamed).unwrap().name, None);
+        unnamed.modes.clear();
+        assert_eq!(output_monitor(&unnamed), None);
+    }
+
+    #[test]
+    fn place_on_scaled_output() {
+        let monitor = MonitorInfo {
+            name: None,
+            rect: WindowRect {
+                x: 1920,
+                y: 0,
+                width: 2560,
+                height: 1440,
+            },
+            scale_factor: 2.0,
+        };
+        let geometry = Geometry {
+            width: Some(Dimension::Percent(50.0)),
+            y: Dimension::Pixels(10),
+            placement: Placement::Bottom,
+            ..Geometry::default()
+        };
+        assert_eq!(
+            surface_placement(&geometry, &monitor, 30),
+            SurfacePlacement {
+                anchor_bottom: true,
+                margin: (10, 0),
+                width: 640,
+                height: 30,
+            }
+        );
+        assert_eq!(
+            surface_placement(&Geometry::default(), &monitor, 30),
+            SurfacePlacement {
+                anchor_bottom: false,
+                margin: (0, 0),
+                width: 1280,
+                height: 30,
+            }
+        );
+    }
+}
@@ -0,0 +1,326 @@
+use std::ops::Range;
+
+/// Number of items skipped by the page-next and page-previous actions in the bar layout
+const BAR_PAGE_SIZE: usize = 10;
+
+/// Number of items, that the bar layout measures at once, far more than fit on a page
+const BAR_WINDOW: usize = 200;
+
+/// How the menu arranges the input and the items
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Layout {
+    /// A single line with the input followed by the items, like dmenu
+    Bar,
+    /// The input above a vertical list of `lines` items
+    List { lines: u16 },
+    /// The input above a grid of items, filled row by row
+    Grid { columns: u16, rows: u16 },
+}
+
+/// Selection movements, their effect depends on the [`Layout`]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Move {
+    /// The following item, in input order
+    Next,
+    /// The preceding item, in input order
+    Previous,
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Layout {
+    /// A list of `lines` items, 0 lines is the bar layout
+    pub fn with_lines(lines: u16) -> Layout {
+        match lines {
+            0 => Layout::Bar,
+            lines => Layout::List { lines },
+        }
+    }
+
+    /// Parses a number of list lines like `10`, `0` is the bar layout
+    pub fn parse_lines(spec: &str) -> Result<Layout, String> {
+        spec.parse()
+            .map(Layout::with_lines)
+            .map_err(|_| format!("Invalid number of lines: {}", spec))
+    }
+
+    /// Parses a grid size like `8x4`: 8 columns and 4 rows
+    pub fn parse_grid(spec: &str) -> Result<Layout, String> {
+        let size = spec
+            .split_once('x')
+            .and_then(|(columns, rows)| Some((columns.parse().ok()?, rows.parse().ok()?)));
+        match size {
+            Some((columns, rows)) if columns > 0 && rows > 0 => Ok(Layout::Grid { columns, rows }),
+            _ => Err(format!("Invalid grid size, expected COLSxROWS: {}", spec)),
+        }
+    }
+
+    /// Number of lines of the window, including the input line
+    pub fn rows(&self) -> u32 {
+        match self {
+            Layout::Bar => 1,
+            Layout::List { lines } => *lines as u32 + 1,
+            Layout::Grid { rows, .. } => *rows as u32 + 1,
+        }
+    }
+
+    /// Number of items, that are shown at once
+    pub fn page_size(&self) -> usize {
+        match self {
+            Layout::Bar => BAR_PAGE_SIZE,
+            Layout::List { lines } => *lines as usize,
+            Layout::Grid { columns, rows } => *columns as usize * *rows as usize,
+        }
+    }
+
+    /// Number of items in a line, that is scrolled as a whole
+    fn columns(&self) -> usize {
+        match self {
+            Layout::Grid { columns, .. } => *columns as usize,
+            _ => 1,
+        }
+    }
+
+    /// Position of the item, that is selected after moving from `position` in a view
+    /// of `count` items.
+    ///
+    /// Moving past the first or last item wraps around. Without a selected item, the
+    /// first item is selected when moving forward, the last one when moving backwards.
+    pub fn navigate(&self, movement: Move, position: Option<usize>, count: usize) -> Option<usize> {
+        if count == 0 {
+            return None;
+        }
+        let columns = self.columns();
+        let vertical = matches!(movement, Move::Up | Move::Down) && columns > 1;
+        let forward = matches!(movement, Move::Next | Move::Down | Move::Right);
+
+        let next = match (position, vertical, forward) {
+            (None, _, true) => 0,
+            (None, _, false) => count - 1,
+            (Some(position), false, true) => (position + 1) % count,
+            (Some(position), false, false) => (position + count - 1) % count,
+            (Some(position), true, true) if position + columns < count => position + columns,
+            // wrap to the first row, in the same column
+            (Some(position), true, true) => position % columns,
+            (Some(position), true, false) if position >= columns => position - columns,
+            // wrap to the last row, that has an item in the same column
+            (Some(position), true, false) => {
+                let last = (count - 1) / columns * columns + position % columns;
+                if last < count {
+                    last
+                } else {
+                    last - columns
+                }
+            }
+        };
+        Some(next)
+    }
+
+    /// Position of the first shown item, see [`scroll_offset`].
+    ///
+    /// Grids scroll by whole rows.
+    pub fn scroll(&self, offset: usize, active: Option<usize>, count: usize) -> usize {
+        let columns = self.columns();
+        let lines = self.page_size() / columns;
+        let rows = (count + columns - 1) / columns;
+        scroll_offset(offset / columns, active.map(|a| a / columns), rows, lines) * columns
+    }
+}
+
+/// Position of the first shown item of a list with `lines` lines.
+///
+/// Scrolls as little as possible from `offset`, so that the `active` position is shown,
+/// and avoids empty lines, when there are enough items to fill them.
+pub fn scroll_offset(offset: usize, active: Option<usize>, count: usize, lines: usize) -> usize {
+    let offset = offset.min(count.saturating_sub(lines));
+    match active {
+        Some(active) if active < offset => active,
+        Some(active) if active >= offset + lines => active + 1 - lines,
+        _ => offset,
+    }
+}
+
+/// Positions of the visible items, that the bar layout measures and pages.
+///
+/// Measuring all items on every frame is too slow for long streamed inputs, so the bar
+/// only sees the window of [`BAR_WINDOW`] items containing the `active` position.
+pub fn bar_window(active: Option<usize>, count: usize) -> Range<usize> {
+    let start = active.unwrap_or(0) / BAR_WINDOW * BAR_WINDOW;
+    start.min(count)..(start + BAR_WINDOW).min(count)
+}
+
+/// Positions of the items shown in the bar layout, on the page with the `active` item.
+///
+/// Pages are filled from the first item on, like in dmenu. `widths` are the widths of all
+/// items, `separator` the space between two items and `indicators` the widths of the
+/// "<" and ">" indicators, which take space when items precede or follow the page.
+/// `more` tells, whether items precede or follow the measured `widths`, see
+/// [`bar_window`]. A page contains at least one item, even when it is wider than
+/// `available`.
+pub fn page_range(
+    widths: &[f32],
+    separator: f32,
+    available: f32,
+    indicators: (f32, f32),
+    more: (bool, bool),
+    active: usize,
+) -> Range<usize> {
+    let mut start = 0;
+    while start < widths.len() {
+        let end = page_end(widths, separator, available, indicators, more, start);
+        if active < end || end == widths.len() {
+            return start..end;
+        }
+        start = end;
+    }
+    0..0
+}
+
+fn page_end(
+    widths: &[f32],
+    separator: f32,
+    available: f32,
+    (before, after): (f32, f32),
+    (more_before, more_after): (bool, bool),
+    start: usize,
+) -> usize {
+    let available = if start > 0 || more_before {
+        available - before
+    } else {
+        available
+    };
+    let mut used = widths[start];
+    let mut end = start + 1;
+    while end < widths.len() {
+        let next = used + separator + widths[end];
+        // the last item needs no room for the ">" indicator
+        let limit = if end + 1 < widths.len() || more_after {
+            available - after
+        } else {
+            available
+        };
+        if next > limit {
+            break;
+        }
+        used = next;
+        end += 1;
+    }
+    end
+}
+
+#[cfg(test)]
+mod test {
+    use crate::layout::{bar_window, page_range, scroll_offset, Layout, Move, BAR_WINDOW};
+
+    const NONE: (bool, bool) = (false, false);
+
+    #[test]
+    fn scroll_to_active() {
+        assert_eq!(scroll_offset(0, Some(3), 20, 5), 0);
+        assert_eq!(scroll_offset(0, Some(5), 20, 5), 1);
+        assert_eq!(scroll_offset(4, Some(9), 20, 5), 5);
+        assert_eq!(scroll_offset(4, Some(2), 20, 5), 2);
+        assert_eq!(scroll_offset(4, None, 20, 5), 4);
+        // the filtered list got shorter
+        assert_eq!(scroll_offset(10, Some(0), 3, 5), 0);
+        assert_eq!(scroll_offset(10, None, 7, 5), 2);
+
+        let grid = Layout::parse_grid("3x2").unwrap();
+        assert_eq!(grid.scroll(0, Some(7), 20), 3);
+        assert_eq!(grid.scroll(9, Some(4), 20), 3);
+    }
+
+    #[test]
+    fn navigate_linear() {
+        for layout in [Layout::Bar, Layout::with_lines(5)] {
+            assert_eq!(layout.navigate(Move::Right, Some(2), 4), Some(3));
+            assert_eq!(layout.navigate(Move::Down, Some(3), 4), Some(0));
+            assert_eq!(layout.navigate(Move::Up, Some(0), 4), Some(3));
+            assert_eq!(layout.navigate(Move::Left, None, 4), Some(3));
+            assert_eq!(layout.navigate(Move::Next, None, 4), Some(0));
+            assert_eq!(layout.navigate(Move::Next, None, 0), None);
+        }
+    }
+
+    #[test]
+    fn navigate_grid() {
+        // 0 1 2
+        // 3 4 5
+        // 6 7
+        let grid = Layout::Grid {
+            columns: 3,
+            rows: 2,
+        };
+        assert_eq!(grid.navigate(Move::Right, Some(2), 8), Some(3));
+        assert_eq!(grid.navigate(Move::Left, Some(0), 8), Some(7));
+        assert_eq!(grid.navigate(Move::Down, Some(1), 8), Some(4));
+        assert_eq!(grid.navigate(Move::Down, Some(5), 8), Some(2));
+        assert_eq!(grid.navigate(Move::Down, Some(7), 8), Some(1));
+        assert_eq!(grid.navigate(Move::Up, Some(4), 8), Some(1));
+        assert_eq!(grid.navigate(Move::Up, Some(1), 8), Some(7));
+        assert_eq!(grid.navigate(Move::Up, Some(2), 8), Some(5));
+        assert_eq!(grid.navigate(Move::Next, Some(2), 8), Some(3));
+
+        assert_eq!(grid.page_size(), 6);
+        assert_eq!(grid.rows(), 3);
+        let largest = Layout::parse_grid(&format!("1x{}", u16::MAX)).unwrap();
+        assert_eq!(largest.rows(), 65536);
+        assert_eq!(Layout::with_lines(u16::MAX).rows(), 65536);
+        assert!(Layout::parse_grid("3x0").is_err());
+        assert!(Layout::parse_grid("3").is_err());
+        assert_eq!(Layout::parse_lines("0"), Ok(Layout::Bar));
+        assert!(Layout::parse_lines("-1").is_err());
+    }
+
+    #[test]
+    fn pages_by_width() {
+        let widths = [30.0, 40.0, 50.0, 20.0, 60.0];
+        // pages: [30, 40] [50, 20] [60]
+        assert_eq!(page_range(&widths, 5.0, 100.0, (10.0, 10.0), NONE, 0), 0..2);
+        assert_eq!(page_range(&widths, 5.0, 100.0, (10.0, 10.0), NONE, 1), 0..2);
+        assert_eq!(page_range(&widths, 5.0, 100.0, (10.0, 10.0), NONE, 3), 2..4);
+        assert_eq!(page_range(&widths, 5.0, 100.0, (10.0, 10.0), NONE, 4), 4..5);
+        // the last item does not need space for the ">" indicator
+        assert_eq!(
+            page_range(&widths[..3], 5.0, 130.0, (10.0, 10.0), NONE, 0),
+            0..3
+        );
+        assert_eq!(
+            page_range(&widths[..3], 5.0, 129.0, (10.0, 10.0), NONE, 2),
+            2..3
+        );
+        // items wider than the bar get a page of their own
+        assert_eq!(
+            page_range(&[200.0, 10.0], 5.0, 100.0, (0.0, 0.0), NONE, 1),
+            1..2
+        );
+        assert_eq!(page_range(&[], 5.0, 100.0, (0.0, 0.0), NONE, 0), 0..0);
+        // items beyond the measured window need the indicators
+        let pair = &widths[..2];
+        assert_eq!(page_range(pair, 5.0, 75.0, (10.0, 10.0), NONE, 0), 0..2);
+        assert_eq!(
+            page_range(pair, 5.0, 75.0, (10.0, 10.0), (true, false), 0),
+            0..1
+        );
+        assert_eq!(
+            page_range(pair, 5.0, 75.0, (10.0, 10.0), (false, true), 0),
+            0..1
+        );
+    }
+
+    #[test]
+    fn bar_window_around_active() {
+        let count = 100_000;
+        assert_eq!(bar_window(None, count), 0..BAR_WINDOW);
+        assert_eq!(bar_window(Some(BAR_WINDOW - 1), count), 0..BAR_WINDOW);
+        assert_eq!(
+            bar_window(Some(BAR_WINDOW + 5), count),
+            BAR_WINDOW..2 * BAR_WINDOW
+        );
+        assert_eq!(bar_window(Some(count - 1), count).end, count);
+        assert_eq!(bar_window(None, 3), 0..3);
+        assert_eq!(bar_window(None, 0), 0..0);
+    }
+}
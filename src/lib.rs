@@ -0,0 +1,54 @@
+//! A keyboard controlled menu, that can be embedded in applications.
+//!
+//! ```no_run
+//! use tmenu2::{run_menu, MenuItem, TMenuSettings};
+//!
+//! let items = ["firefox", "terminal"]
+//!     .iter()
+//!     .enumerate()
+//!     .map(|(index, text)| MenuItem::create(text, index))
+//!     .collect();
+//! let settings = TMenuSettings::builder().prompt("Run").case_insensitive(true).build();
+//! match run_menu(items, settings) {
+//!     Ok(selection) => println!("{:?}", selection.values()),
+//!     Err(aborted) => eprintln!("{}", aborted),
+//! }
+//! ```
+
+mod app;
+mod args;
+mod config;
+mod error;
+mod filter;
+mod geometry;
+mod history;
+mod item_bar;
+mod item_parser;
+mod keymap;
+#[cfg(target_os = "linux")]
+mod layer_shell;
+mod layout;
+mod menu_item;
+mod monitor;
+mod output;
+mod runner;
+#[cfg(test)]
+mod scenario;
+mod stdin_reader;
+mod styles;
+mod theme;
+mod tmenu;
+mod tmenu_settings;
+
+pub use error::TMenuError;
+pub use filter::{create_filter_factory, CustomFilter, Filter, FilterFactory, Match, MatchMode};
+pub use geometry::{Dimension, Geometry, Placement};
+pub use keymap::{Key, Keymap};
+pub use layout::Layout;
+pub use menu_item::{ItemState, MenuItem};
+pub use monitor::MonitorSelection;
+pub use output::{OutputFormat, Selection};
+pub use runner::{run, run_menu, Aborted};
+pub use theme::Theme;
+pub use tmenu::{ExitState, MainAction};
+pub use tmenu_settings::{Backend, MultiOrder, TMenuSettings, TMenuSettingsBuilder};
@@ -1,9 +1,10 @@
 use iced_core::{alignment};
 
 use crate::styles;
+use crate::theme::Theme;
 use crate::tmenu::MainAction;
 use std::fmt::{Debug, Formatter};
-use iced_native::widget::{Text, Container};
+use iced_native::widget::{Container, Row, Text};
 use iced_native::Length;
 
 type Element<'a> = iced_native::Element<'a, MainAction, iced_wgpu::Renderer>;
@@ -14,16 +15,21 @@ pub struct MenuItem {
     pub text: String,
     pub mnemonic: Option<String>,
     pub value: Option<String>,
+    pub icon: Option<String>,
+    pub hint: Option<String>,
+    /// Matched by filters instead of the value, the displayed text of items with
+    /// separate fields
+    pub search_text: Option<String>,
     pub state: ItemState,
-    pub width: Option<f64>,
+    pub marked: bool,
 }
 
 impl Debug for MenuItem {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "{}: '{}' mn={:?} value={:?}",
-            self.index, self.text, self.mnemonic, self.value
+            "{}: '{}' mn={:?} value={:?} icon={:?} hint={:?}",
+            self.index, self.text, self.mnemonic, self.value, self.icon, self.hint
         )
     }
 }
@@ -52,8 +58,36 @@ impl MenuItem {
             text,
             mnemonic,
             value,
+            icon: None,
+            hint: None,
+            search_text: None,
             state: ItemState::Visible,
-            width: None,
+            marked: false,
+        }
+    }
+
+    /// Creates an item from separately passed fields, without parsing the item syntax
+    pub fn from_fields(
+        index: usize,
+        text: &str,
+        value: Option<String>,
+        mnemonic: Option<String>,
+    ) -> MenuItem {
+        let search_text = Some(text.to_string());
+        let text = match &mnemonic {
+            Some(mn) => format!("({}) {}", mn, text),
+            None => text.to_string(),
+        };
+        MenuItem {
+            index,
+            text,
+            mnemonic,
+            value,
+            icon: None,
+            hint: None,
+            search_text,
+            state: ItemState::Visible,
+            marked: false,
         }
     }
 
@@ -79,19 +113,46 @@ impl MenuItem {
         self.value.as_deref().unwrap_or(self.text.as_str())
     }
 
-    pub fn view<'a>(&self) -> Option<Element<'a>> {
+    /// The text, that filters match: the value of plain items and the displayed text,
+    /// without mnemonic, of items with separate fields
+    pub fn search_text(&self) -> &str {
+        self.search_text.as_deref().unwrap_or_else(|| self.value())
+    }
+
+    pub fn view<'a>(&self, theme: &Theme, width: Length) -> Option<Element<'a>> {
         if self.state == ItemState::Hidden {
             return None;
         }
-        let text = Text::new(self.text.clone()).vertical_alignment(alignment::Vertical::Center);
-        let text = Container::new(text)
-            .height(Length::Units(30))
-            .align_y(alignment::Vertical::Center);
-        let result = match self.state {
-            ItemState::Active => text.style(styles::ActiveItem),
-            _ => text.style(styles::DefaultItem),
-        };
-        Some(result.into())
+        let mut content = Row::new().spacing(6);
+        if let Some(icon) = &self.icon {
+            content = content.push(Text::new(icon.clone()));
+        }
+        let mnemonic = self
+            .mnemonic
+            .as_ref()
+            .map(|mn| format!("({})", mn))
+            .filter(|mn| self.text.starts_with(mn.as_str()));
+        if let Some(mnemonic) = mnemonic {
+            let text = self.text[mnemonic.len()..].trim_start().to_string();
+            content = content
+                .push(Text::new(mnemonic).color(theme.mnemonic))
+                .push(Text::new(text));
+        } else {
+            content = content.push(Text::new(self.text.clone()));
+        }
+        if let Some(hint) = &self.hint {
+            content = content.push(Text::new(hint.clone()).color(theme.hint));
+        }
+        let text = Container::new(content)
+            .width(width)
+            .height(Length::Units(theme.item_height))
+            .align_y(alignment::Vertical::Center)
+            .style(styles::Item::new(
+                theme,
+                self.state == ItemState::Active,
+                self.marked,
+            ));
+        Some(text.into())
     }
 
     pub fn visible(&self) -> bool {
@@ -0,0 +1,302 @@
+use std::fmt::{Display, Formatter};
+use std::process::Command;
+
+use serde::{Deserialize, Deserializer};
+
+use crate::geometry::WindowRect;
+
+/// The monitor, that shows the menu
+#[derive(Debug, Clone, PartialEq)]
+pub enum MonitorSelection {
+    /// The primary monitor, or the first one, when there is no primary monitor
+    Primary,
+    /// Position in the list of available monitors
+    Index(usize),
+    /// A monitor name, like `DP-1`
+    Name(String),
+    /// The monitor with the focused window, as reported by the window manager.
+    ///
+    /// It is found by the position of the focused output or workspace, and by the output
+    /// name, when the position matches no monitor.
+    Focused,
+    /// The monitor under the mouse pointer
+    Pointer,
+}
+
+impl MonitorSelection {
+    pub fn parse(spec: &str) -> MonitorSelection {
+        match spec {
+            "primary" => MonitorSelection::Primary,
+            "focused" => MonitorSelection::Focused,
+            "pointer" => MonitorSelection::Pointer,
+            spec => match spec.parse() {
+                Ok(index) => MonitorSelection::Index(index),
+                Err(_) => MonitorSelection::Name(spec.to_string()),
+            },
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for MonitorSelection {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Value {
+            Index(usize),
+            Text(String),
+        }
+        Ok(match Value::deserialize(deserializer)? {
+            Value::Index(index) => MonitorSelection::Index(index),
+            Value::Text(text) => MonitorSelection::parse(&text),
+        })
+    }
+}
+
+/// A monitor reported by the windowing system, positions and sizes in physical pixels
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorInfo {
+    pub name: Option<String>,
+    pub rect: WindowRect,
+    pub scale_factor: f64,
+}
+
+impl Display for MonitorInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at {},{} size {}x{} scale {}",
+            self.name.as_deref().unwrap_or("<unnamed>"),
+            self.rect.x,
+            self.rect.y,
+            self.rect.width,
+            self.rect.height,
+            self.scale_factor
+        )
+    }
+}
+
+/// Index of the selected monitor in `monitors`.
+///
+/// `None`, when the selected monitor does not exist or cannot be determined on this
+/// platform. The focused output is found by its position, see [`FocusedOutput::find`].
+/// The pointer position falls back to the focused monitor.
+pub fn select_monitor(selection: &MonitorSelection, monitors: &[MonitorInfo]) -> Option<usize> {
+    match selection {
+        MonitorSelection::Primary => None,
+        MonitorSelection::Index(index) => Some(*index).filter(|i| *i < monitors.len()),
+        MonitorSelection::Name(name) => monitor_named(monitors, name),
+        MonitorSelection::Focused => focused_output().and_then(|focused| focused.find(monitors)),
+        MonitorSelection::Pointer => pointer_position()
+            .and_then(|(x, y)| monitor_at(monitors, x, y))
+            .or_else(|| select_monitor(&MonitorSelection::Focused, monitors)),
+    }
+}
+
+/// Finds a monitor by its name.
+///
+/// Wayland reports names like `Dell U2720Q (DP-1)`, that contain the output name in
+/// parentheses.
+fn monitor_named(monitors: &[MonitorInfo], name: &str) -> Option<usize> {
+    let output = format!("({})", name);
+    monitors.iter().position(|m| {
+        let monitor_name = m.name.as_deref().unwrap_or_default();
+        monitor_name == name || monitor_name.ends_with(&output)
+    })
+}
+
+fn monitor_at(monitors: &[MonitorInfo], x: i32, y: i32) -> Option<usize> {
+    monitors.iter().position(|m| {
+        let rect = &m.rect;
+        (rect.x..rect.x + rect.width as i32).contains(&x)
+            && (rect.y..rect.y + rect.height as i32).contains(&y)
+    })
+}
+
+/// The focused output, as reported by the IPC of sway, Hyprland or i3
+#[derive(Debug, PartialEq)]
+struct FocusedOutput {
+    name: Option<String>,
+    /// Top left corner of the output, or of the focused workspace on it
+    position: Option<(i32, i32)>,
+}
+
+impl FocusedOutput {
+    /// Index of the monitor at the position of the output.
+    ///
+    /// The names of the window manager and of the windowing system differ, e.g. X11
+    /// names outputs by RandR, so the name is only used when no position matches.
+    fn find(&self, monitors: &[MonitorInfo]) -> Option<usize> {
+        self.position
+            .and_then(|(x, y)| monitor_at(monitors, x, y))
+            .or_else(|| monitor_named(monitors, self.name.as_deref()?))
+    }
+}
+
+/// The focused output, from the IPC of sway, Hyprland or i3
+fn focused_output() -> Option<FocusedOutput> {
+    let env = |name| std::env::var_os(name).is_some();
+    if env("SWAYSOCK") {
+        let outputs = command_json("swaymsg", &["-t", "get_outputs", "-r"])?;
+        find_focused(&outputs, "name", Some("rect"))
+    } else if env("HYPRLAND_INSTANCE_SIGNATURE") {
+        let monitors = command_json("hyprctl", &["monitors", "-j"])?;
+        find_focused(&monitors, "name", None)
+    } else if env("I3SOCK") || env("DISPLAY") {
+        let workspaces = command_json("i3-msg", &["-t", "get_workspaces"])?;
+        find_focused(&workspaces, "output", Some("rect"))
+    } else {
+        None
+    }
+}
+
+/// Pointer position in screen coordinates, from Hyprland or xdotool on X11
+fn pointer_position() -> Option<(i32, i32)> {
+    if std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+        // "x, y"
+        let output = command_output("hyprctl", &["cursorpos"])?;
+        let (x, y) = output.trim().split_once(',')?;
+        Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+    } else if std::env::var_os("WAYLAND_DISPLAY").is_none() {
+        // X=123\nY=456\nSCREEN=0\nWINDOW=...
+        let output = command_output("xdotool", &["getmouselocation", "--shell"])?;
+        let value = |key: &str| {
+            output
+                .lines()
+                .find_map(|line| line.strip_prefix(key))
+                .and_then(|v| v.trim().parse().ok())
+        };
+        Some((value("X=")?, value("Y=")?))
+    } else {
+        None
+    }
+}
+
+/// The first object in a JSON array with `"focused": true`.
+///
+/// The output name is the value of `name`, the position is taken from the `x` and `y`
+/// values of the object `rect`, or of the object itself, when `rect` is `None`.
+fn find_focused(
+    objects: &serde_json::Value,
+    name: &str,
+    rect: Option<&str>,
+) -> Option<FocusedOutput> {
+    let focused = objects
+        .as_array()?
+        .iter()
+        .find(|o| o["focused"].as_bool() == Some(true))?;
+    let rect = match rect {
+        Some(key) => &focused[key],
+        None => focused,
+    };
+    let coordinate = |key: &str| rect[key].as_i64().and_then(|v| i32::try_from(v).ok());
+    Some(FocusedOutput {
+        name: focused[name].as_str().map(str::to_string),
+        position: coordinate("x").zip(coordinate("y")),
+    })
+}
+
+fn command_json(program: &str, args: &[&str]) -> Option<serde_json::Value> {
+    serde_json::from_str(&command_output(program, args)?).ok()
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if output.status.success() {
+        String::from_utf8(output.stdout).ok()
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::geometry::WindowRect;
+    use crate::monitor::{
+        find_focused, monitor_at, monitor_named, select_monitor, FocusedOutput, MonitorInfo,
+        MonitorSelection,
+    };
+
+    fn monitors() -> Vec<MonitorInfo> {
+        let monitor = |name: &str, x, width| MonitorInfo {
+            name: Some(name.to_string()),
+            rect: WindowRect {
+                x,
+                y: 0,
+                width,
+                height: 1080,
+            },
+            scale_factor: 1.0,
+        };
+        vec![
+            monitor("eDP-1", 0, 1920),
+            monitor("Dell (DP-1)", 1920, 2560),
+        ]
+    }
+
+    #[test]
+    fn select_by_index_and_name() {
+        let monitors = monitors();
+        assert_eq!(MonitorSelection::parse("1"), MonitorSelection::Index(1));
+        assert_eq!(
+            MonitorSelection::parse("DP-1"),
+            MonitorSelection::Name("DP-1".to_string())
+        );
+        assert_eq!(
+            select_monitor(&MonitorSelection::parse("1"), &monitors),
+            Some(1)
+        );
+        assert_eq!(
+            select_monitor(&MonitorSelection::parse("2"), &monitors),
+            None
+        );
+        assert_eq!(monitor_named(&monitors, "eDP-1"), Some(0));
+        assert_eq!(monitor_named(&monitors, "DP-1"), Some(1));
+        assert_eq!(monitor_named(&monitors, "HDMI-1"), None);
+    }
+
+    #[test]
+    fn select_by_position() {
+        let monitors = monitors();
+        assert_eq!(monitor_at(&monitors, 0, 0), Some(0));
+        assert_eq!(monitor_at(&monitors, 1920, 500), Some(1));
+        assert_eq!(monitor_at(&monitors, 4480, 500), None);
+    }
+
+    #[test]
+    fn focused_output_from_ipc() {
+        let monitors = monitors();
+        // swaymsg -t get_outputs
+        let outputs = serde_json::json!([
+            {"name": "eDP-1", "focused": false, "rect": {"x": 0, "y": 0}},
+            {"name": "DP-1", "focused": true, "rect": {"x": 1920, "y": 0}}
+        ]);
+        let focused = find_focused(&outputs, "name", Some("rect")).unwrap();
+        assert_eq!(
+            focused,
+            FocusedOutput {
+                name: Some("DP-1".to_string()),
+                position: Some((1920, 0)),
+            }
+        );
+        assert_eq!(focused.find(&monitors), Some(1));
+        // hyprctl monitors -j
+        let outputs = serde_json::json!([
+            {"name": "eDP-1", "focused": true, "x": 0, "y": 0},
+            {"name": "DP-1", "focused": false, "x": 1920, "y": 0}
+        ]);
+        let focused = find_focused(&outputs, "name", None).unwrap();
+        assert_eq!(focused.find(&monitors), Some(0));
+        // i3-msg -t get_workspaces, the X11 monitor names differ from the i3 output names
+        let workspaces = serde_json::json!([
+            {"output": "DisplayPort-0", "focused": true, "rect": {"x": 1920, "y": 20}}
+        ]);
+        let focused = find_focused(&workspaces, "output", Some("rect")).unwrap();
+        assert_eq!(focused.find(&monitors), Some(1));
+        // the name is used, when the position is unknown
+        let outputs = serde_json::json!([{"name": "DP-1", "focused": true}]);
+        let focused = find_focused(&outputs, "name", Some("rect")).unwrap();
+        assert_eq!(focused.position, None);
+        assert_eq!(focused.find(&monitors), Some(1));
+        assert_eq!(find_focused(&serde_json::json!({}), "name", None), None);
+    }
+}
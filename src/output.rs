@@ -0,0 +1,153 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use crate::menu_item::MenuItem;
+
+/// Items or typed input accepted by the user
+#[derive(Debug, Clone)]
+pub struct Selection {
+    /// The selected items, empty when the typed input was accepted
+    pub items: Vec<MenuItem>,
+    /// The input, when the selection was accepted
+    pub input: String,
+}
+
+impl Selection {
+    /// Values of the selected items, or the typed input
+    pub fn values(&self) -> Vec<&str> {
+        if self.items.is_empty() {
+            vec![self.input.as_str()]
+        } else {
+            self.items.iter().map(MenuItem::value).collect()
+        }
+    }
+
+    /// Formats the selection, one entry per item
+    pub fn format(&self, format: OutputFormat) -> Vec<String> {
+        if self.items.is_empty() {
+            vec![format_selection(format, None, &self.input)]
+        } else {
+            self.items
+                .iter()
+                .map(|item| format_selection(format, Some(item), &self.input))
+                .collect()
+        }
+    }
+}
+
+/// Where accepted selections go
+#[derive(Debug, Clone)]
+pub enum Output {
+    /// Printed in the output format, like on the command line
+    Stdout,
+    /// Collected by an application, that embeds the menu
+    Collect(Rc<RefCell<Vec<Selection>>>),
+}
+
+/// How selected items are printed on stdout
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum OutputFormat {
+    /// The item value, one per line
+    Plain,
+    /// One JSON object per line
+    Json,
+    /// The input position of the item, one per line
+    Index,
+    /// The item value, terminated by a NUL character
+    Nul,
+}
+
+impl OutputFormat {
+    pub fn parse(name: &str) -> Option<OutputFormat> {
+        match name {
+            "plain" => Some(OutputFormat::Plain),
+            "json" => Some(OutputFormat::Json),
+            "index" => Some(OutputFormat::Index),
+            "nul" => Some(OutputFormat::Nul),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct JsonSelection<'a> {
+    value: &'a str,
+    text: Option<&'a str>,
+    index: Option<usize>,
+    mnemonic: Option<&'a str>,
+    typed_input: &'a str,
+    /// The value was typed by the user and is not one of the items
+    undefined: bool,
+}
+
+/// Formats a selected item, or the typed input when `item` is `None`, including the
+/// terminating newline or NUL character.
+pub fn format_selection(
+    format: OutputFormat,
+    item: Option<&MenuItem>,
+    typed_input: &str,
+) -> String {
+    match format {
+        OutputFormat::Plain => format!("{}\n", selected_value(item, typed_input)),
+        OutputFormat::Nul => format!("{}\0", selected_value(item, typed_input)),
+        OutputFormat::Index => match item {
+            Some(item) => format!("{}\n", item.index),
+            None => "-1\n".to_string(),
+        },
+        OutputFormat::Json => {
+            let selection = JsonSelection {
+                value: selected_value(item, typed_input),
+                text: item.map(|i| i.text.as_str()),
+                index: item.map(|i| i.index),
+                mnemonic: item.and_then(|i| i.mnemonic.as_deref()),
+                typed_input,
+                undefined: item.is_none(),
+            };
+            let mut json = serde_json::to_string(&selection).expect("Serializing strings");
+            json.push('\n');
+            json
+        }
+    }
+}
+
+fn selected_value<'a>(item: Option<&'a MenuItem>, typed_input: &'a str) -> &'a str {
+    item.map(MenuItem::value).unwrap_or(typed_input)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::menu_item::MenuItem;
+    use crate::output::{format_selection, OutputFormat};
+
+    #[test]
+    fn format_item() {
+        let item = MenuItem::create("(m) key=text", 3);
+        let item = Some(&item);
+        assert_eq!(format_selection(OutputFormat::Plain, item, "m"), "key\n");
+        assert_eq!(format_selection(OutputFormat::Nul, item, "m"), "key\0");
+        assert_eq!(format_selection(OutputFormat::Index, item, "m"), "3\n");
+        assert_eq!(
+            format_selection(OutputFormat::Json, item, "m"),
+            r#"{"value":"key","text":"(m) text","index":3,"mnemonic":"m","typed_input":"m","undefined":false}"#
+                .to_string()
+                + "\n"
+        );
+    }
+
+    #[test]
+    fn format_undefined() {
+        assert_eq!(
+            format_selection(OutputFormat::Plain, None, "typed"),
+            "typed\n"
+        );
+        assert_eq!(format_selection(OutputFormat::Index, None, "typed"), "-1\n");
+        assert_eq!(
+            format_selection(OutputFormat::Json, None, "a\"b"),
+            r#"{"value":"a\"b","text":null,"index":null,"mnemonic":null,"typed_input":"a\"b","undefined":true}"#
+                .to_string()
+                + "\n"
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use iced_winit::settings::SettingsWindowConfigurator;
+use iced_winit::winit::dpi::{PhysicalPosition, PhysicalSize, Position, Size};
+use iced_winit::winit::event_loop::EventLoopWindowTarget;
+use iced_winit::winit::monitor::MonitorHandle;
+use iced_winit::winit::window::WindowBuilder;
+use iced_winit::Mode;
+
+use crate::app::TMenuApp;
+use crate::error::TMenuError;
+use crate::geometry::{Geometry, WindowRect};
+#[cfg(target_os = "linux")]
+use crate::layer_shell;
+use crate::menu_item::MenuItem;
+use crate::monitor::{select_monitor, MonitorInfo, MonitorSelection};
+use crate::output::{Output, Selection};
+use crate::tmenu::ExitState;
+use crate::tmenu_settings::{Backend, TMenuSettings};
+
+/// Why [`run_menu`] returned without a selection
+#[derive(Debug, Clone, PartialEq)]
+pub enum Aborted {
+    /// The user closed the menu
+    ByUser,
+    /// The menu could not be shown, or there were no items to choose from
+    Failed(TMenuError),
+}
+
+impl std::fmt::Display for Aborted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Aborted::ByUser => write!(f, "Aborted"),
+            Aborted::Failed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Aborted {}
+
+/// Shows a menu with `items` and returns the selection, once the user accepts it.
+///
+/// The items are indexed in the given order, `settings.available_options` are replaced.
+/// Nothing is read from stdin or printed on stdout. The menu runs on the calling thread,
+/// which has to be the main thread on most platforms.
+pub fn run_menu(mut items: Vec<MenuItem>, settings: TMenuSettings) -> Result<Selection, Aborted> {
+    for (index, item) in items.iter_mut().enumerate() {
+        item.index = index;
+    }
+    let selections = Rc::new(RefCell::new(Vec::new()));
+    let settings = TMenuSettings {
+        available_options: items,
+        read_stdin: false,
+        output: Output::Collect(selections.clone()),
+        exit_state: Rc::new(RefCell::new(ExitState::Continue)),
+        ..settings
+    };
+
+    match run(settings).map_err(Aborted::Failed)? {
+        ExitState::Abort | ExitState::Continue => Err(Aborted::ByUser),
+        ExitState::Failed(e) => Err(Aborted::Failed(e)),
+        // the selection, that closed the menu
+        ExitState::Exit | ExitState::CustomInput => {
+            selections.take().into_iter().last().ok_or(Aborted::ByUser)
+        }
+    }
+}
+
+/// Shows the menu with the backend of the settings, until it is closed.
+///
+/// A failed menu is returned as error, never as [`ExitState::Failed`].
+pub fn run(settings: TMenuSettings) -> Result<ExitState, TMenuError> {
+    if settings.missing_options() {
+        return Err(TMenuError::NoOptions);
+    }
+    let exit_state = settings.exit_state.clone();
+
+    let renderer_settings = iced_wgpu::Settings {
+        antialiasing: Some(iced_wgpu::settings::Antialiasing::MSAAx4),
+        default_font: settings.theme.load_font(),
+        default_text_size: settings.theme.font_size,
+        ..iced_wgpu::Settings::from_env()
+    };
+
+    #[cfg(target_os = "linux")]
+    let settings = match run_layer_shell(settings, renderer_settings.clone())? {
+        Some(settings) => settings,
+        None => return final_state(&exit_state),
+    };
+    #[cfg(not(target_os = "linux"))]
+    if settings.backend == Backend::LayerShell {
+        let message = "The layer shell backend is only available on Linux";
+        return Err(TMenuError::Window(message.to_string()));
+    }
+
+    run_winit(settings, renderer_settings)?;
+    final_state(&exit_state)
+}
+
+fn final_state(exit_state: &RefCell<ExitState>) -> Result<ExitState, TMenuError> {
+    match exit_state.replace(ExitState::Continue) {
+        ExitState::Failed(error) => Err(error),
+        state => Ok(state),
+    }
+}
+
+#[derive(Debug)]
+struct PlaceOnTopConfigurator {
+    settings: SettingsWindowConfigurator,
+    geometry: Geometry,
+    /// Logical height of the menu content
+    content_height: u32,
+    monitor: MonitorSelection,
+    verbose: bool,
+}
+
+impl PlaceOnTopConfigurator {
+    fn choose_monitor<M>(&self, window_target: &EventLoopWindowTarget<M>) -> Option<MonitorHandle> {
+        let monitors: Vec<MonitorHandle> = window_target.available_monitors().collect();
+        let infos: Vec<MonitorInfo> = monitors.iter().map(monitor_info).collect();
+        if self.verbose {
+            eprintln!("Monitors:");
+            for (index, info) in infos.iter().enumerate() {
+                eprintln!("  {}: {}", index, info);
+            }
+        }
+
+        let selected = select_monitor(&self.monitor, &infos);
+        if selected.is_none() && self.monitor != MonitorSelection::Primary {
+            eprintln!(
+                "Monitor {:?} not found, using the primary monitor",
+                self.monitor
+            );
+        }
+        selected
+            .map(|index| monitors[index].clone())
+            .or_else(|| window_target.primary_monitor())
+            .or_else(|| monitors.first().cloned())
+    }
+}
+
+fn monitor_info(monitor: &MonitorHandle) -> MonitorInfo {
+    MonitorInfo {
+        name: monitor.name(),
+        rect: WindowRect {
+            x: monitor.position().x,
+            y: monitor.position().y,
+            width: monitor.size().width,
+            height: monitor.size().height,
+        },
+        scale_factor: monitor.scale_factor(),
+    }
+}
+
+impl<M> iced_winit::window_configurator::WindowConfigurator<M> for PlaceOnTopConfigurator {
+    fn configure_builder(
+        self,
+        window_target: &EventLoopWindowTarget<M>,
+        window_builder: WindowBuilder,
+    ) -> WindowBuilder {
+        let mut window_builder = self
+            .settings
+            .configure_builder(window_target, window_builder);
+        window_builder = window_builder.with_always_on_top(true);
+        if let Some(monitor) = self.choose_monitor(window_target) {
+            let info = monitor_info(&monitor);
+            let rect = self
+                .geometry
+                .resolve(info.rect, info.scale_factor, self.content_height);
+            window_builder = window_builder
+                .with_position(Position::Physical(PhysicalPosition::new(rect.x, rect.y)))
+                .with_inner_size(Size::Physical(PhysicalSize::new(rect.width, rect.height)));
+        }
+
+        window_builder
+    }
+}
+
+/// Shows the menu on a layer shell surface, when the backend is selected or detected.
+///
+/// Returns the settings, when the menu should use winit instead.
+#[cfg(target_os = "linux")]
+fn run_layer_shell(
+    app_settings: TMenuSettings,
+    renderer_settings: iced_wgpu::Settings,
+) -> Result<Option<TMenuSettings>, TMenuError> {
+    let on_wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+    let layer_shell = match app_settings.backend {
+        Backend::Winit => return Ok(Some(app_settings)),
+        Backend::Auto if !on_wayland => return Ok(Some(app_settings)),
+        Backend::Auto => match layer_shell::connect() {
+            Ok(layer_shell) => layer_shell,
+            Err(e) => {
+                if app_settings.verbose {
+                    eprintln!("{}, using winit", e);
+                }
+                return Ok(Some(app_settings));
+            }
+        },
+        Backend::LayerShell => layer_shell::connect().map_err(TMenuError::Window)?,
+    };
+    layer_shell
+        .run(app_settings, renderer_settings)
+        .map_err(TMenuError::Window)?;
+    Ok(None)
+}
+
+fn run_winit(
+    app_settings: TMenuSettings,
+    renderer_settings: iced_wgpu::Settings,
+) -> Result<(), TMenuError> {
+    let window_configurator = PlaceOnTopConfigurator {
+        settings: SettingsWindowConfigurator {
+            window: iced_winit::settings::Window {
+                resizable: false,
+                decorations: false,
+                transparent: false,
+                always_on_top: true,
+                ..Default::default()
+            },
+            id: Some("tmenu".to_string()),
+            mode: Mode::Windowed,
+        },
+        geometry: app_settings.geometry.clone(),
+        content_height: app_settings.content_height(),
+        monitor: app_settings.monitor.clone(),
+        verbose: app_settings.verbose,
+    };
+
+    iced_winit::application::run_with_window_configurator::<
+        TMenuApp,
+        iced_futures::executor::ThreadPool,
+        iced_wgpu::window::Compositor,
+        _,
+    >(app_settings, renderer_settings, window_configurator, true)
+    .map_err(|e| TMenuError::Window(e.to_string()))
+}
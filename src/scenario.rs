@@ -0,0 +1,138 @@
+//! Scripted test scenarios, that drive a [`TMenu`] without a window.
+//!
+//! A scenario is a list of steps separated by `;`, like
+//! `type "fo"; next; accept; expect "foo"`. Arguments are quoted strings, that contain
+//! neither `"` nor `;`.
+//!
+//! * `type "TEXT"` appends to the input, like typing it
+//! * `key "ctrl+n"` presses a key, that is resolved by the keymap
+//! * `receive "A" "B"` delivers items read from stdin, `close` closes stdin
+//! * action names of the keymap, like `next`, `accept` or `toggle-mark`, trigger the action
+//! * `expect "A" "B"` asserts the values printed since the last `expect`
+//! * `visible "A" "B"` asserts the visible items, in the order they are shown
+//! * `active "A"` asserts the active item, `active` without argument asserts none is active
+//! * `input "TEXT"` asserts the input
+//! * `exit "STATE"` asserts the exit state: `continue`, `exit`, `custom-input`, `abort` or
+//!   `no-options`
+
+use crate::error::TMenuError;
+use crate::keymap::{parse_action, Key};
+use crate::menu_item::{ItemState, MenuItem};
+use crate::tmenu::{ExitState, MainAction, TMenu};
+use crate::tmenu_settings::TMenuSettings;
+
+/// Settings with the given options and everything else at its default
+pub fn settings(options: &[&str]) -> TMenuSettings {
+    TMenuSettings {
+        available_options: options
+            .iter()
+            .enumerate()
+            .map(|(index, text)| MenuItem::create(text, index))
+            .collect(),
+        ..TMenuSettings::default()
+    }
+}
+
+/// Runs the steps of `script` against `menu` and panics on the first failed step
+pub fn run(menu: &mut TMenu, script: &str) {
+    for step in script.split(';').map(str::trim).filter(|s| !s.is_empty()) {
+        let (command, args) = parse_step(step).unwrap_or_else(|e| panic!("{}: {}", step, e));
+        if let Err(e) = run_step(menu, &command, &args) {
+            panic!("step '{}' failed: {}", step, e);
+        }
+    }
+}
+
+fn run_step(menu: &mut TMenu, command: &str, args: &[String]) -> Result<(), String> {
+    match command {
+        "type" => {
+            let input = format!("{}{}", menu.input(), single(args)?);
+            menu.update(MainAction::TextChanged(input));
+        }
+        "key" => {
+            let key = Key::parse(single(args)?)?;
+            menu.update(key.into());
+        }
+        "receive" => menu.update(MainAction::ItemsReceived(args.to_vec())),
+        "close" => menu.update(MainAction::InputClosed),
+        "expect" => {
+            let printed: Vec<String> = menu
+                .take_output()
+                .iter()
+                .flat_map(|selection| selection.values())
+                .map(str::to_string)
+                .collect();
+            compare("printed", &printed, args)?;
+        }
+        "visible" => {
+            let visible: Vec<String> = menu
+                .visible_items()
+                .map(|i| i.value().to_string())
+                .collect();
+            compare("visible", &visible, args)?;
+        }
+        "active" => {
+            let active: Vec<String> = menu
+                .visible_items()
+                .filter(|i| i.state == ItemState::Active)
+                .map(|i| i.value().to_string())
+                .collect();
+            compare("active", &active, args)?;
+        }
+        "input" => compare("input", &[menu.input().to_string()], args)?,
+        "exit" => {
+            let expected = match single(args)? {
+                "continue" => ExitState::Continue,
+                "exit" => ExitState::Exit,
+                "custom-input" => ExitState::CustomInput,
+                "abort" => ExitState::Abort,
+                "no-options" => ExitState::Failed(TMenuError::NoOptions),
+                other => return Err(format!("Unknown exit state '{}'", other)),
+            };
+            if menu.exit_state() != expected {
+                return Err(format!("exit state is {:?}", menu.exit_state()));
+            }
+        }
+        action => {
+            let action = parse_action(action).ok_or("Unknown step")?;
+            if !args.is_empty() {
+                return Err("Actions take no arguments".to_string());
+            }
+            menu.update(action);
+        }
+    }
+    Ok(())
+}
+
+fn single(args: &[String]) -> Result<&str, String> {
+    match args {
+        [arg] => Ok(arg),
+        _ => Err(format!("Expected one argument, got {}", args.len())),
+    }
+}
+
+fn compare(what: &str, actual: &[String], expected: &[String]) -> Result<(), String> {
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(format!("{} is {:?}, expected {:?}", what, actual, expected))
+    }
+}
+
+/// Splits a step into the command and its quoted arguments
+fn parse_step(step: &str) -> Result<(String, Vec<String>), String> {
+    let (command, mut rest) = step.split_once(char::is_whitespace).unwrap_or((step, ""));
+    let mut args = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return Ok((command.to_string(), args));
+        }
+        let quoted = rest
+            .strip_prefix('"')
+            .ok_or_else(|| format!("Expected a quoted argument at '{}'", rest))?;
+        let end = quoted.find('"').ok_or("Missing closing quote")?;
+        args.push(quoted[..end].to_string());
+        rest = &quoted[end + 1..];
+    }
+}
@@ -0,0 +1,72 @@
+use std::hash::Hash;
+use std::io::{BufRead, ErrorKind};
+
+use iced_futures::futures::channel::mpsc;
+use iced_futures::futures::{stream, StreamExt};
+use iced_futures::BoxStream;
+use iced_native::subscription::Recipe;
+use iced_native::{event, Event, Hasher};
+
+use crate::error::TMenuError;
+use crate::tmenu::MainAction;
+
+/// Maximum number of lines passed to the menu with a single message
+const MAX_BATCH: usize = 1024;
+
+/// Reads menu items from stdin on a background thread, while the menu is already shown.
+///
+/// All lines available at once are passed to the menu in a single
+/// [`MainAction::ItemsReceived`], [`MainAction::InputClosed`] follows the last line.
+/// Reading stops at the first invalid line, that is reported with
+/// [`MainAction::InputFailed`].
+pub struct StdinReader;
+
+impl Recipe<Hasher, (Event, event::Status)> for StdinReader {
+    type Output = MainAction;
+
+    fn hash(&self, state: &mut Hasher) {
+        std::any::TypeId::of::<Self>().hash(state);
+    }
+
+    fn stream(
+        self: Box<Self>,
+        _input: BoxStream<(Event, event::Status)>,
+    ) -> BoxStream<Self::Output> {
+        let (sender, receiver) = mpsc::unbounded();
+        std::thread::spawn(move || {
+            let stdin = std::io::stdin();
+            for (number, line) in stdin.lock().lines().enumerate() {
+                let line = line.map_err(|e| match e.kind() {
+                    ErrorKind::InvalidData => TMenuError::InvalidUtf8 { line: number + 1 },
+                    _ => TMenuError::Io(format!("Failed reading stdin: {}", e)),
+                });
+                let failed = line.is_err();
+                if sender.unbounded_send(line).is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        Box::pin(
+            receiver
+                .ready_chunks(MAX_BATCH)
+                .flat_map(|chunk| stream::iter(chunk_actions(chunk)))
+                .chain(stream::once(async { MainAction::InputClosed })),
+        )
+    }
+}
+
+/// The lines of a chunk, followed by the error that ended reading
+fn chunk_actions(chunk: Vec<Result<String, TMenuError>>) -> Vec<MainAction> {
+    let mut lines = Vec::with_capacity(chunk.len());
+    let mut failed = None;
+    for line in chunk {
+        match line {
+            Ok(line) => lines.push(line),
+            Err(e) => failed = Some(MainAction::InputFailed(e)),
+        }
+    }
+    std::iter::once(MainAction::ItemsReceived(lines))
+        .chain(failed)
+        .collect()
+}
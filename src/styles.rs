@@ -1,9 +1,8 @@
-use iced_style::container::Style;
-use iced_core::Color;
 use iced_core::Padding;
-use iced_style::Background;
+use iced_style::{container, rule, text_input};
+use iced_style::{Background, Color};
 
-pub struct DefaultItem;
+use crate::theme::Theme;
 
 pub const TEXT_INPUT_PADDING: Padding = Padding {
     top: 0,
@@ -12,19 +11,126 @@ pub const TEXT_INPUT_PADDING: Padding = Padding {
     left: 6,
 };
 
-impl iced_style::container::StyleSheet for DefaultItem {
-    fn style(&self) -> Style {
-        Style::default()
+/// Text color of the whole menu, the background is the window background
+pub struct Window {
+    pub foreground: Color,
+}
+
+impl container::StyleSheet for Window {
+    fn style(&self) -> container::Style {
+        container::Style {
+            text_color: Some(self.foreground),
+            ..container::Style::default()
+        }
+    }
+}
+
+/// The prompt label left of the input
+pub struct Prompt {
+    pub foreground: Color,
+    pub background: Color,
+}
+
+impl container::StyleSheet for Prompt {
+    fn style(&self) -> container::Style {
+        container::Style {
+            text_color: Some(self.foreground),
+            background: Some(Background::Color(self.background)),
+            ..container::Style::default()
+        }
+    }
+}
+
+/// Colors of a menu item, depending on whether it is active and marked
+pub struct Item {
+    text_color: Option<Color>,
+    background: Option<Color>,
+    border_color: Option<Color>,
+}
+
+impl Item {
+    pub fn new(theme: &Theme, active: bool, marked: bool) -> Item {
+        match (active, marked) {
+            (true, marked) => Item {
+                text_color: Some(theme.selected_foreground),
+                background: Some(theme.selected_background),
+                // marked items keep a frame in the marked color while active
+                border_color: Some(theme.marked_background).filter(|_| marked),
+            },
+            (false, true) => Item {
+                text_color: None,
+                background: Some(theme.marked_background),
+                border_color: None,
+            },
+            (false, false) => Item {
+                text_color: None,
+                background: None,
+                border_color: None,
+            },
+        }
+    }
+}
+
+impl container::StyleSheet for Item {
+    fn style(&self) -> container::Style {
+        container::Style {
+            text_color: self.text_color,
+            background: self.background.map(Background::Color),
+            border_width: if self.border_color.is_some() {
+                2.0
+            } else {
+                0.0
+            },
+            border_color: self.border_color.unwrap_or(Color::TRANSPARENT),
+            ..container::Style::default()
+        }
     }
 }
 
-pub struct ActiveItem;
+/// The text input, drawn without frame on the window background
+pub struct Input {
+    pub foreground: Color,
+    pub placeholder: Color,
+    pub selection: Color,
+}
+
+impl text_input::StyleSheet for Input {
+    fn active(&self) -> text_input::Style {
+        text_input::Style {
+            background: Background::Color(Color::TRANSPARENT),
+            ..text_input::Style::default()
+        }
+    }
+
+    fn focused(&self) -> text_input::Style {
+        self.active()
+    }
+
+    fn placeholder_color(&self) -> Color {
+        self.placeholder
+    }
+
+    fn value_color(&self) -> Color {
+        self.foreground
+    }
+
+    fn selection_color(&self) -> Color {
+        self.selection
+    }
+}
+
+/// A thin line between items
+pub struct Separator {
+    pub color: Color,
+}
 
-impl iced_style::container::StyleSheet for ActiveItem {
-    fn style(&self) -> Style {
-        Style {
-            background: Some(Background::Color(Color::from_rgb8(150, 150, 230))),
-            ..Style::default()
+impl rule::StyleSheet for Separator {
+    fn style(&self) -> rule::Style {
+        rule::Style {
+            color: self.color,
+            width: 1,
+            radius: 0.0,
+            fill_mode: rule::FillMode::Full,
         }
     }
 }
@@ -0,0 +1,330 @@
+use std::path::Path;
+use std::process::Command;
+
+use iced_core::Color;
+use serde::Deserialize;
+
+/// Colors, font and sizes of the menu
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub foreground: Color,
+    pub background: Color,
+    pub selected_foreground: Color,
+    pub selected_background: Color,
+    /// Background of items marked in --multi mode
+    pub marked_background: Color,
+    pub mnemonic: Color,
+    /// Colors of the prompt label, the selected item colors by default, like in dmenu
+    pub prompt_foreground: Option<Color>,
+    pub prompt_background: Option<Color>,
+    /// Color of the placeholder text of the empty input
+    pub placeholder: Color,
+    pub hint: Color,
+    /// Color of messages about invalid input, like an invalid regex
+    pub error: Color,
+    pub separator_color: Color,
+    /// Font family or path of a font file, `None` uses the renderer default font
+    pub font: Option<String>,
+    pub font_size: u16,
+    pub item_height: u16,
+    /// Space between two items, the separator is centered in it
+    pub item_spacing: u16,
+    pub separator: Separator,
+}
+
+/// What is drawn between two items
+#[derive(Debug, Clone, PartialEq)]
+pub enum Separator {
+    None,
+    Line,
+    Text(String),
+}
+
+impl Separator {
+    /// `none`, `line` or any other text, that is shown between items
+    pub fn parse(spec: &str) -> Separator {
+        match spec {
+            "none" => Separator::None,
+            "line" => Separator::Line,
+            text => Separator::Text(text.to_string()),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            foreground: Color::BLACK,
+            background: Color::WHITE,
+            selected_foreground: Color::BLACK,
+            selected_background: Color::from_rgb8(150, 150, 230),
+            marked_background: Color::from_rgb8(170, 210, 170),
+            mnemonic: Color::from_rgb8(40, 40, 160),
+            prompt_foreground: None,
+            prompt_background: None,
+            placeholder: Color::from_rgb8(90, 90, 90),
+            hint: Color::from_rgb(0.45, 0.45, 0.45),
+            error: Color::from_rgb8(200, 40, 40),
+            separator_color: Color::from_rgba(0.6, 0.6, 0.6, 0.51),
+            font: None,
+            font_size: 20,
+            item_height: 30,
+            item_spacing: 12,
+            separator: Separator::Line,
+        }
+    }
+}
+
+impl Theme {
+    pub fn prompt_colors(&self) -> (Color, Color) {
+        (
+            self.prompt_foreground.unwrap_or(self.selected_foreground),
+            self.prompt_background.unwrap_or(self.selected_background),
+        )
+    }
+
+    /// Sets the font from a dmenu like font spec: `FAMILY`, `FAMILY:size=SIZE` or `:size=SIZE`
+    pub fn set_font(&mut self, spec: &str) -> Result<(), String> {
+        let mut parts = spec.split(':');
+        let family = parts.next().unwrap_or_default().trim();
+        if !family.is_empty() {
+            self.font = Some(family.to_string());
+        }
+        for property in parts {
+            match property.trim().split_once('=') {
+                Some(("size" | "pixelsize", size)) => {
+                    self.font_size = size
+                        .trim()
+                        .parse::<f32>()
+                        .ok()
+                        .filter(|s| *s >= 1.0)
+                        .map(|s| s.round() as u16)
+                        .ok_or_else(|| format!("Invalid font size in '{}'", spec))?;
+                }
+                // other fontconfig properties like antialias=true are not supported
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the configured font file.
+    ///
+    /// Font families are resolved to files with `fc-match`. When the font cannot be
+    /// loaded, a warning is printed and the renderer default font is used.
+    pub fn load_font(&self) -> Option<&'static [u8]> {
+        let font = self.font.as_deref()?;
+        let path = if Path::new(font).is_file() {
+            font.to_string()
+        } else {
+            let output = Command::new("fc-match")
+                .args(["--format=%{file}", font])
+                .output();
+            match output {
+                Ok(output) if output.status.success() && !output.stdout.is_empty() => {
+                    String::from_utf8_lossy(&output.stdout).into_owned()
+                }
+                _ => {
+                    eprintln!("Font not found: {}", font);
+                    return None;
+                }
+            }
+        };
+        match std::fs::read(&path) {
+            // the renderer keeps the font for the whole runtime of tmenu
+            Ok(bytes) => Some(Box::leak(bytes.into_boxed_slice())),
+            Err(e) => {
+                eprintln!("Failed reading font {}: {}", path, e);
+                None
+            }
+        }
+    }
+}
+
+/// Parses colors like `#rgb`, `#rrggbb` or `#rrggbbaa`
+pub fn parse_color(spec: &str) -> Result<Color, String> {
+    let hex = spec.strip_prefix('#').unwrap_or(spec);
+    let digits: Option<Vec<u8>> = hex
+        .chars()
+        .map(|c| c.to_digit(16).map(|d| d as u8))
+        .collect();
+    let channels: Vec<u8> = match (digits, hex.len()) {
+        (Some(d), 3) => d.iter().map(|d| d * 17).chain(Some(255)).collect(),
+        (Some(d), 6 | 8) => d
+            .chunks(2)
+            .map(|c| c[0] * 16 + c[1])
+            .chain(Some(255))
+            .take(4)
+            .collect(),
+        _ => return Err(format!("Invalid color: '{}'", spec)),
+    };
+    Ok(Color::from_rgba8(
+        channels[0],
+        channels[1],
+        channels[2],
+        channels[3] as f32 / 255.0,
+    ))
+}
+
+/// Theme settings of the `[theme]` config file table
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ThemeOptions {
+    foreground: Option<String>,
+    background: Option<String>,
+    selected_foreground: Option<String>,
+    selected_background: Option<String>,
+    marked_background: Option<String>,
+    mnemonic: Option<String>,
+    /// The placeholder color, named before the prompt label had colors of its own
+    prompt: Option<String>,
+    prompt_foreground: Option<String>,
+    prompt_background: Option<String>,
+    placeholder: Option<String>,
+    hint: Option<String>,
+    error: Option<String>,
+    separator_color: Option<String>,
+    font: Option<String>,
+    font_size: Option<u16>,
+    item_height: Option<u16>,
+    item_spacing: Option<u16>,
+    separator: Option<String>,
+}
+
+impl ThemeOptions {
+    /// Options of `self`, overridden by the options set in `other`
+    pub fn merge(self, other: &ThemeOptions) -> ThemeOptions {
+        let other = other.clone();
+        ThemeOptions {
+            foreground: other.foreground.or(self.foreground),
+            background: other.background.or(self.background),
+            selected_foreground: other.selected_foreground.or(self.selected_foreground),
+            selected_background: other.selected_background.or(self.selected_background),
+            marked_background: other.marked_background.or(self.marked_background),
+            mnemonic: other.mnemonic.or(self.mnemonic),
+            prompt: other.prompt.or(self.prompt),
+            prompt_foreground: other.prompt_foreground.or(self.prompt_foreground),
+            prompt_background: other.prompt_background.or(self.prompt_background),
+            placeholder: other.placeholder.or(self.placeholder),
+            hint: other.hint.or(self.hint),
+            error: other.error.or(self.error),
+            separator_color: other.separator_color.or(self.separator_color),
+            font: other.font.or(self.font),
+            font_size: other.font_size.or(self.font_size),
+            item_height: other.item_height.or(self.item_height),
+            item_spacing: other.item_spacing.or(self.item_spacing),
+            separator: other.separator.or(self.separator),
+        }
+    }
+
+    pub fn apply(&self, theme: &mut Theme) -> Result<(), String> {
+        let placeholder = self.placeholder.clone().or_else(|| self.prompt.clone());
+        let colors = [
+            (&self.foreground, &mut theme.foreground),
+            (&self.background, &mut theme.background),
+            (&self.selected_foreground, &mut theme.selected_foreground),
+            (&self.selected_background, &mut theme.selected_background),
+            (&self.marked_background, &mut theme.marked_background),
+            (&self.mnemonic, &mut theme.mnemonic),
+            (&placeholder, &mut theme.placeholder),
+            (&self.hint, &mut theme.hint),
+            (&self.error, &mut theme.error),
+            (&self.separator_color, &mut theme.separator_color),
+        ];
+        for (spec, color) in colors {
+            if let Some(spec) = spec {
+                *color = parse_color(spec)?;
+            }
+        }
+        let prompt_colors = [
+            (&self.prompt_foreground, &mut theme.prompt_foreground),
+            (&self.prompt_background, &mut theme.prompt_background),
+        ];
+        for (spec, color) in prompt_colors {
+            if let Some(spec) = spec {
+                *color = Some(parse_color(spec)?);
+            }
+        }
+
+        if let Some(font) = &self.font {
+            theme.set_font(font)?;
+        }
+        // an explicit font-size wins over the size of the font spec
+        let sizes = [
+            (self.font_size, &mut theme.font_size),
+            (self.item_height, &mut theme.item_height),
+            (self.item_spacing, &mut theme.item_spacing),
+        ];
+        for (value, size) in sizes {
+            if let Some(value) = value {
+                *size = value;
+            }
+        }
+        if let Some(separator) = &self.separator {
+            theme.separator = Separator::parse(separator);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use iced_core::Color;
+
+    use crate::theme::{parse_color, Separator, Theme, ThemeOptions};
+
+    #[test]
+    fn parse_colors() {
+        assert_eq!(parse_color("#ff0000").unwrap(), Color::from_rgb8(255, 0, 0));
+        assert_eq!(parse_color("#0f0").unwrap(), Color::from_rgb8(0, 255, 0));
+        assert_eq!(parse_color("0000ff").unwrap(), Color::from_rgb8(0, 0, 255));
+        assert_eq!(
+            parse_color("#00000000").unwrap(),
+            Color::from_rgba8(0, 0, 0, 0.0)
+        );
+        assert!(parse_color("#12345").is_err());
+        assert!(parse_color("#gggggg").is_err());
+        assert!(parse_color("red").is_err());
+    }
+
+    #[test]
+    fn prompt_colors_default_to_selected() {
+        let mut theme = Theme::default();
+        theme.selected_background = Color::from_rgb8(0, 85, 119);
+        assert_eq!(
+            theme.prompt_colors(),
+            (Color::BLACK, Color::from_rgb8(0, 85, 119))
+        );
+        theme.prompt_foreground = Some(Color::WHITE);
+        assert_eq!(theme.prompt_colors().0, Color::WHITE);
+    }
+
+    #[test]
+    fn prompt_key_sets_the_placeholder_color() {
+        let options: ThemeOptions = toml::from_str(r##"prompt = "#888888""##).unwrap();
+        let mut theme = Theme::default();
+        options.apply(&mut theme).unwrap();
+        assert_eq!(theme.placeholder, Color::from_rgb8(0x88, 0x88, 0x88));
+        assert_eq!(theme.prompt_foreground, None);
+
+        let options: ThemeOptions =
+            toml::from_str("prompt = \"#888888\"\nplaceholder = \"#999999\"").unwrap();
+        options.apply(&mut theme).unwrap();
+        assert_eq!(theme.placeholder, Color::from_rgb8(0x99, 0x99, 0x99));
+    }
+
+    #[test]
+    fn font_spec() {
+        let mut theme = Theme::default();
+        theme.set_font("DejaVu Sans Mono:size=14").unwrap();
+        assert_eq!(theme.font.as_deref(), Some("DejaVu Sans Mono"));
+        assert_eq!(theme.font_size, 14);
+
+        theme.set_font(":pixelsize=11.6:antialias=true").unwrap();
+        assert_eq!(theme.font.as_deref(), Some("DejaVu Sans Mono"));
+        assert_eq!(theme.font_size, 12);
+
+        assert!(theme.set_font("mono:size=big").is_err());
+        assert_eq!(Separator::parse("|"), Separator::Text("|".to_string()));
+    }
+}
@@ -1,284 +1,564 @@
-use std::cell::Cell;
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
-use iced_core::keyboard::{Event, KeyCode, Modifiers};
-use iced_core::{Length, Padding};
-use iced_wgpu::{text_input, Container, Row, Rule, TextInput};
-use iced_winit::{Application, Command, Program, Subscription};
+use iced_core::keyboard::{KeyCode, Modifiers};
 
+use crate::error::TMenuError;
 use crate::filter::{create_filter_factory, Filter, FilterFactory, Match};
+use crate::history::{self, History};
+use crate::item_parser::ItemParser;
+use crate::keymap::Keymap;
+use crate::layout::{Layout, Move};
 use crate::menu_item::{ItemState, MenuItem};
-use crate::styles;
-use crate::tmenu_settings::TMenuSettings;
-
-type Element<'a, Message> = iced_winit::Element<'a, Message, iced_wgpu::Renderer>;
+use crate::output::Selection;
+use crate::tmenu_settings::{MultiOrder, TMenuSettings};
 
+/// The state of the menu: items, input and the accepted selections.
+///
+/// Runs without a window, the iced program in [`crate::app`] renders it and feeds it
+/// with [`MainAction`]s.
 #[derive(Debug)]
 pub struct TMenu {
     available_options: Vec<MenuItem>,
+    /// Indices of the matching options, sorted by relevance
+    visible: Vec<usize>,
     auto_accept: bool,
     case_insensitive: bool,
     allow_undefined: bool,
+    /// The typed input is accepted, when no option matches, see [`TMenu::accepts_input`]
+    dmenu: bool,
     fuzzy: bool,
+    multi_select: bool,
+    multi_order: MultiOrder,
+    /// The input is masked and overwritten, once it is no longer needed
+    password: bool,
+    /// Selections, that are not printed yet
+    output: Vec<Selection>,
+    /// Indices of the marked options, in selection order
+    marked: Vec<usize>,
     text_changed: bool,
+    /// Options are still read from stdin
+    loading: bool,
     verbose: bool,
     input: String,
-    exit_state: Rc<Cell<ExitState>>,
+    /// The input was replaced, not typed, the cursor belongs at its end
+    input_replaced: bool,
+    exit_state: Rc<RefCell<ExitState>>,
     filter_factory: Box<dyn FilterFactory>,
-
-    text_input: text_input::State,
+    /// Why the input is no valid filter, e.g. an invalid regex
+    filter_error: Option<String>,
+    item_parser: ItemParser,
+    keymap: Keymap,
+    history: Option<PathBuf>,
+    history_frecency: HashMap<String, u32>,
+    /// Frecency of each option from the history, used to order equally relevant matches
+    frecency: Vec<u32>,
+    layout: Layout,
+    /// Value or index of the item to activate, until it has been received
+    select: Option<String>,
 }
 
 impl TMenu {
-    fn action_abort(&mut self) {
-        self.exit_state.set(ExitState::Abort);
-    }
-
-    fn select_next(&mut self, offset: isize) {
-        assert!(offset == -1 || offset == 1);
-        let mut visible = all_visible(&mut self.available_options);
-        let count = visible.len() as isize;
-        if count > 0 {
-            if let Some((last_active_index, last_active)) = visible
-                .iter_mut()
-                .map(|(_, i)| i)
-                .enumerate()
-                .find(|(_, i)| i.state == ItemState::Active)
-            {
-                last_active.state = ItemState::Visible;
-                let mut next_active = last_active_index as isize + offset;
-                if next_active >= count {
-                    next_active -= count;
-                } else if next_active < 0 {
-                    next_active += count;
-                }
-                if let Some((_, i)) = visible.get_mut(next_active as usize) {
-                    i.state = ItemState::Active;
-                }
-            } else if offset == 1 {
-                visible.first_mut().unwrap().1.state = ItemState::Active;
-            } else if offset == -1 {
-                visible.last_mut().unwrap().1.state = ItemState::Active;
-            }
+    pub fn new(flags: TMenuSettings) -> TMenu {
+        let filter_factory = create_filter_factory(&flags);
+        if flags.verbose {
+            eprintln!("\n\n{:?}", filter_factory);
         }
-    }
+        let history_frecency = flags
+            .history
+            .as_deref()
+            .map(load_frecency)
+            .unwrap_or_default();
+        let frecency: Vec<u32> = flags
+            .available_options
+            .iter()
+            .map(|i| history_frecency.get(i.value()).copied().unwrap_or(0))
+            .collect();
+        let mut visible: Vec<usize> = (0..flags.available_options.len()).collect();
+        visible.sort_by_key(|i| Reverse(frecency.get(*i).copied().unwrap_or(0)));
 
-    fn take_text(&mut self) {
-        if let Some((_, item)) = find_active(&mut self.available_options) {
-            self.input = item.text.to_string();
+        let mut menu = TMenu {
+            visible,
+            available_options: flags.available_options,
+            auto_accept: flags.auto_accept && !flags.multi_select,
+            case_insensitive: flags.case_insensitive,
+            allow_undefined: flags.allow_undefined,
+            dmenu: flags.dmenu,
+            fuzzy: flags.fuzzy,
+            multi_select: flags.multi_select,
+            multi_order: flags.multi_order,
+            password: flags.password,
+            output: Vec::new(),
+            marked: Vec::new(),
+            text_changed: false,
+            loading: flags.read_stdin,
+            verbose: flags.verbose,
+            input: String::new(),
+            input_replaced: false,
+            exit_state: flags.exit_state,
+            filter_factory,
+            filter_error: None,
+            item_parser: ItemParser::new(flags.input_format, flags.columns),
+            keymap: flags.keymap,
+            history: flags.history,
+            history_frecency,
+            frecency,
+            layout: flags.layout,
+            select: flags.select,
+        };
+        if !menu.allow_undefined {
+            if let Some(first) = menu.visible.first() {
+                menu.available_options[*first].state = ItemState::Active;
+            }
+        }
+        if let Some(query) = flags.query {
+            menu.update(MainAction::TextChanged(query));
+            menu.input_replaced = true;
         }
+        menu.apply_select();
+        menu
     }
-}
-
-impl Program for TMenu {
-    type Renderer = iced_wgpu::Renderer;
-    type Message = MainAction;
 
-    fn update(&mut self, message: MainAction) -> Command<Self::Message> {
-        self.text_input.focus();
+    pub fn update(&mut self, message: MainAction) {
         match message {
             MainAction::Focus => {}
+            MainAction::KeyPressed(key_code, modifiers) => {
+                if let Some(action) = self.keymap.action(key_code, modifiers) {
+                    self.update(action);
+                }
+            }
             MainAction::Abort => self.action_abort(),
-            MainAction::Exit => {
-                if let Some((_, result)) = find_active(&mut self.available_options) {
-                    println!("{}", result.value());
-                    self.exit_state.set(ExitState::Exit);
-                } else if self.allow_undefined && !self.input.is_empty() {
-                    println!("{}", self.input);
-                    self.exit_state.set(ExitState::Exit);
-                } else {
-                    self.action_abort()
+            MainAction::AcceptInput => {
+                // the typed text is only a result, where custom input is allowed
+                if self.password || (self.accepts_input() && !self.input.is_empty()) {
+                    self.accept_input();
                 }
             }
-            MainAction::Next => self.select_next(1),
+            MainAction::Exit => self.accept(false),
+            MainAction::AcceptContinue => self.accept(true),
+            MainAction::ToggleMark => self.toggle_mark(),
+            MainAction::Next => self.select(Move::Next),
             MainAction::NextTab => {
-                self.select_next(1);
+                self.select(Move::Next);
                 self.take_text();
             }
-            MainAction::Previous => self.select_next(-1),
+            MainAction::Previous => self.select(Move::Previous),
             MainAction::PreviousTab => {
-                self.select_next(-1);
+                self.select(Move::Previous);
                 self.take_text();
             }
+            MainAction::Up => self.select(Move::Up),
+            MainAction::Down => self.select(Move::Down),
+            MainAction::Left => self.select(Move::Left),
+            MainAction::Right => self.select(Move::Right),
+            MainAction::First => self.select_position(0),
+            MainAction::Last => self.select_position(self.visible.len().saturating_sub(1)),
+            MainAction::PageNext => self.select_page(1),
+            MainAction::PagePrevious => self.select_page(-1),
+            MainAction::ClearInput => self.set_input(String::new()),
+            MainAction::DeleteWord => self.set_input(delete_word(&self.input).to_string()),
             MainAction::TextChanged(new_input) => {
-                let filter = self.filter_factory.create(&new_input);
-
-                apply_filter(&mut self.available_options, filter, !self.allow_undefined);
-                self.input = new_input;
-
-                if self.auto_accept
-                    && self
-                        .available_options
-                        .iter()
-                        .filter(|e| e.visible())
-                        .count()
-                        == 1
-                {
-                    if let Some((_, result)) = find_active(&mut self.available_options) {
-                        println!("{}", result.value());
-                        self.exit_state.set(ExitState::Exit);
-                    }
+                self.replace_input(new_input);
+                self.apply_input();
+                self.maybe_auto_accept();
+            }
+            MainAction::ItemsReceived(lines) => self.add_items(lines),
+            MainAction::InputClosed if self.loading => {
+                self.loading = false;
+                // custom input can still be typed, like the dmenu idiom `dmenu < /dev/null`
+                if self.available_options.is_empty() && !self.accepts_input() {
+                    self.exit(ExitState::Failed(TMenuError::NoOptions));
+                } else if !self.input.is_empty() {
+                    self.maybe_auto_accept();
                 }
             }
+            // stdin failed before
+            MainAction::InputClosed => {}
+            MainAction::InputFailed(error) => {
+                self.loading = false;
+                self.exit(ExitState::Failed(error));
+            }
         };
+    }
 
-        Command::none()
-    }
-
-    fn view(&mut self) -> Element<'_, Self::Message> {
-        let main_input = TextInput::new(&mut self.text_input, "option", &self.input, |input| {
-            MainAction::TextChanged(input)
-        })
-        .padding(Padding {
-            top: 5,
-            right: 0,
-            bottom: 5,
-            left: 0,
-        })
-        .on_submit(MainAction::Exit);
-
-        let mut main_container = Row::new();
-        main_container = main_container.push(
-            Container::new(main_input)
-                .width(Length::Units(300))
-                .height(Length::Fill)
-                .max_width(300)
-                .padding(styles::TEXT_INPUT_PADDING),
-        );
-        let mut item_container = Row::new();
+    pub fn input(&self) -> &str {
+        &self.input
+    }
 
-        let start_pos = find_active(&mut self.available_options)
-            .map(|a| a.0.max(2) - 2)
-            .unwrap_or(0);
+    /// Whether the input was replaced since the last call, e.g. by Tab completion
+    pub fn take_input_replaced(&mut self) -> bool {
+        std::mem::take(&mut self.input_replaced)
+    }
 
-        let mut iter = self
-            .available_options
+    /// The selections accepted since the last call
+    pub fn take_output(&mut self) -> Vec<Selection> {
+        std::mem::take(&mut self.output)
+    }
+
+    pub fn exit_state(&self) -> ExitState {
+        self.exit_state.borrow().clone()
+    }
+
+    pub fn should_exit(&self) -> bool {
+        !matches!(*self.exit_state.borrow(), ExitState::Continue)
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.loading
+    }
+
+    pub fn is_password(&self) -> bool {
+        self.password
+    }
+
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    pub fn filter_error(&self) -> Option<&str> {
+        self.filter_error.as_deref()
+    }
+
+    /// The items matching the input, the most relevant first
+    pub fn visible_items(&self) -> impl Iterator<Item = &MenuItem> {
+        self.visible.iter().map(|i| &self.available_options[*i])
+    }
+
+    /// Number of visible items
+    pub fn visible_count(&self) -> usize {
+        self.visible.len()
+    }
+
+    /// Position of the active item in the sorted view of visible items
+    pub fn active_position(&self) -> Option<usize> {
+        self.visible
             .iter()
-            .skip(start_pos)
-            .flat_map(MenuItem::view);
+            .position(|i| self.available_options[*i].state == ItemState::Active)
+    }
+
+    /// Custom input is allowed. Unlike --allow-undefined, dmenu still selects the best
+    /// match, so that the input is only accepted, when nothing matches
+    fn accepts_input(&self) -> bool {
+        self.allow_undefined || self.dmenu
+    }
+
+    fn exit(&self, state: ExitState) {
+        self.exit_state.replace(state);
+    }
+
+    fn action_abort(&mut self) {
+        self.replace_input(String::new());
+        self.exit(ExitState::Abort);
+    }
 
-        if let Some(i) = iter.next() {
-            item_container = item_container.push(i);
+    /// Accepts the marked, the active or the typed option, the menu stays open with
+    /// `keep_open`
+    fn accept(&mut self, keep_open: bool) {
+        if self.password {
+            self.accept_input();
+        } else if !self.marked.is_empty() {
+            let mut marked = self.marked.clone();
+            if let MultiOrder::Input = self.multi_order {
+                marked.sort_unstable();
+            }
+            self.output_items(&marked, keep_open);
+        } else if let Some(index) = find_active(&mut self.available_options).map(|a| a.0) {
+            self.output_items(&[index], keep_open);
+        } else if self.accepts_input() && !self.input.is_empty() {
+            if keep_open {
+                self.output.push(Selection {
+                    items: Vec::new(),
+                    input: self.input.clone(),
+                });
+            } else {
+                self.accept_input();
+            }
+        } else if !keep_open {
+            self.action_abort()
         }
+    }
 
-        item_container = iter.fold(item_container, |c, i| c.push(Rule::vertical(12)).push(i));
+    /// Outputs the options at `indices` and closes the menu, unless `keep_open`
+    fn output_items(&mut self, indices: &[usize], keep_open: bool) {
+        let items: Vec<MenuItem> = indices
+            .iter()
+            .map(|index| self.available_options[*index].clone())
+            .collect();
+        if let Some(path) = &self.history {
+            for item in &items {
+                if let Err(e) = History::append(path, item.value(), history::now()) {
+                    eprintln!("Failed writing history {}: {}", path.display(), e);
+                }
+            }
+        }
+        self.output.push(Selection {
+            items,
+            input: self.input.clone(),
+        });
+        if !keep_open {
+            self.exit(ExitState::Exit);
+        }
+    }
 
-        main_container.push(item_container).into()
+    fn accept_input(&mut self) {
+        // moved, so that no copy of a password remains
+        let input = std::mem::take(&mut self.input);
+        self.output.push(Selection {
+            items: Vec::new(),
+            input,
+        });
+        self.exit(ExitState::CustomInput);
     }
-}
 
-impl Application for TMenu {
-    type Flags = TMenuSettings;
+    fn add_items(&mut self, lines: Vec<String>) {
+        let first_index = self.available_options.len();
+        let mut items = Vec::with_capacity(lines.len());
+        for line in &lines {
+            match self.item_parser.parse_line(line, first_index + items.len()) {
+                Ok(Some(item)) => items.push(item),
+                Ok(None) => {}
+                Err(e) => eprintln!("Skipping invalid item '{}': {}", line, e),
+            }
+        }
 
-    fn new(flags: TMenuSettings) -> (Self, Command<Self::Message>) {
-        let filter_factory = create_filter_factory(&flags);
-        if flags.verbose {
-            eprintln!("\n\n{:?}", filter_factory);
+        self.filter_factory.add_items(&items);
+        self.frecency.extend(
+            items
+                .iter()
+                .map(|i| self.history_frecency.get(i.value()).copied().unwrap_or(0)),
+        );
+        self.available_options.extend(items);
+        self.apply_input();
+        self.apply_select();
+    }
+
+    fn apply_input(&mut self) {
+        if self.password {
+            // there are no options, and the filters would copy the input
+            return;
         }
-        let mut app = TMenu {
-            available_options: flags.available_options,
-            auto_accept: flags.auto_accept,
-            case_insensitive: flags.case_insensitive,
-            allow_undefined: flags.allow_undefined,
-            fuzzy: flags.fuzzy,
-            text_changed: false,
-            verbose: flags.verbose,
-            input: String::new(),
-            exit_state: flags.exit_state,
-            filter_factory,
-            text_input: text_input::State::focused(),
+        let filter = self.filter_factory.create(&self.input);
+
+        self.visible = apply_filter(
+            &mut self.available_options,
+            &self.frecency,
+            filter,
+            !self.allow_undefined,
+        );
+        self.filter_error = if self.input.is_empty() {
+            None
+        } else {
+            self.filter_factory.input_error().map(str::to_string)
         };
-        if !app.allow_undefined {
-            if let Some(first) = app.available_options.first_mut() {
-                first.state = ItemState::Active;
+    }
+
+    fn maybe_auto_accept(&mut self) {
+        if self.auto_accept && !self.loading && self.visible.len() == 1 {
+            if let Some(index) = find_active(&mut self.available_options).map(|a| a.0) {
+                self.output_items(&[index], false);
             }
         }
-        (app, Command::none())
     }
 
-    fn title(&self) -> String {
-        "tmenu".to_string()
+    fn toggle_mark(&mut self) {
+        if !self.multi_select {
+            return;
+        }
+        if let Some(index) = find_active(&mut self.available_options).map(|a| a.0) {
+            let item = &mut self.available_options[index];
+            item.marked = !item.marked;
+            if item.marked {
+                self.marked.push(index);
+            } else {
+                self.marked.retain(|i| *i != index);
+            }
+        }
     }
 
-    fn subscription(&self) -> Subscription<Self::Message> {
-        iced_native::subscription::events_with(global_keyboard_handler)
+    fn select(&mut self, movement: Move) {
+        let next = self
+            .layout
+            .navigate(movement, self.active_position(), self.visible.len());
+        if let Some(next) = next {
+            self.select_position(next);
+        }
     }
 
-    fn should_exit(&self) -> bool {
-        if let ExitState::Continue = self.exit_state.get() {
-            false
-        } else {
-            true
+    fn select_position(&mut self, position: usize) {
+        if let Some(last_active) = self.active_position() {
+            self.available_options[self.visible[last_active]].state = ItemState::Visible;
+        }
+        if let Some(index) = self.visible.get(position) {
+            self.available_options[*index].state = ItemState::Active;
+        }
+    }
+
+    fn select_page(&mut self, pages: isize) {
+        if self.visible.is_empty() {
+            return;
+        }
+        let current = self.active_position().unwrap_or(0) as isize;
+        let last = self.visible.len() as isize - 1;
+        let target = (current + pages * self.layout.page_size() as isize).clamp(0, last);
+        self.select_position(target as usize);
+    }
+
+    /// Activates the item selected by `--select`, once it is available.
+    ///
+    /// Values take precedence over indices. The item stays inactive, when the input
+    /// filters it out.
+    fn apply_select(&mut self) {
+        let index = match &self.select {
+            Some(spec) => self
+                .available_options
+                .iter()
+                .position(|i| i.value() == spec)
+                .or_else(|| {
+                    spec.parse()
+                        .ok()
+                        .filter(|i| *i < self.available_options.len())
+                }),
+            None => return,
+        };
+        if let Some(index) = index {
+            self.select = None;
+            if let Some(position) = self.visible.iter().position(|i| *i == index) {
+                self.select_position(position);
+            }
+        }
+    }
+
+    /// Replaces the input, like typing it would
+    fn set_input(&mut self, input: String) {
+        self.replace_input(input);
+        self.input_replaced = true;
+        self.apply_input();
+    }
+
+    /// Replaces the input, without filtering. A password is overwritten first.
+    fn replace_input(&mut self, input: String) {
+        if self.password {
+            zeroize(&mut self.input);
+        }
+        self.input = input;
+    }
+
+    fn take_text(&mut self) {
+        if let Some((_, item)) = find_active(&mut self.available_options) {
+            self.input = item.text.to_string();
+            self.input_replaced = true;
         }
     }
 }
 
-fn apply_filter<'a, 'b>(
-    items: &'b mut [MenuItem],
+/// Applies `filter` to all items and returns the indices of the matching items,
+/// the most relevant first. Items with the same relevance are ordered by frecency,
+/// then by input order.
+fn apply_filter<'a>(
+    items: &mut [MenuItem],
+    frecency: &[u32],
     mut filter: Box<dyn Filter<'a> + 'a>,
     update_selection: bool,
-) {
+) -> Vec<usize> {
     let previous_active = find_active(items).map(|e| e.0);
 
     let mut match_offset = None;
+    let mut ranked = Vec::new();
 
-    for i in items.iter_mut() {
+    for (index, i) in items.iter_mut().enumerate() {
         let result = filter.match_item(i);
         i.state = match result {
             Match::NoMatch => ItemState::Hidden,
-            Match::Index(index) => {
-                match_offset = Some(index);
+            Match::Index(offset) => {
+                match_offset = Some(offset);
                 ItemState::Visible
             }
-            Match::Match => ItemState::Visible,
+            Match::Match | Match::Score(_) => ItemState::Visible,
+        };
+        if i.visible() {
+            ranked.push((result.rank(), index));
         }
     }
 
-    let mut all_visible = all_visible(items);
-
-    let match_count = all_visible.len();
-    if match_count == 0 {
-        return;
-    }
-
-    if !update_selection {
-        return;
+    ranked.sort_by_key(|(rank, index)| {
+        let frecency = frecency.get(*index).copied().unwrap_or(0);
+        (Reverse(*rank), Reverse(frecency), *index)
+    });
+    let visible: Vec<usize> = ranked.iter().map(|(_, index)| *index).collect();
+
+    if update_selection && !visible.is_empty() {
+        // keep the previous selection, as long as no better match is available
+        let best_rank = ranked[0].0;
+        let previous_active = previous_active.filter(|previous| {
+            ranked
+                .iter()
+                .any(|(rank, index)| index == previous && *rank == best_rank)
+        });
+        select_item(items, &visible, previous_active, match_offset);
     }
 
-    select_item(previous_active, match_offset, &mut all_visible, match_count);
+    visible
 }
 
 fn select_item(
+    items: &mut [MenuItem],
+    visible: &[usize],
     previous_active: Option<usize>,
     match_offset: Option<u32>,
-    all_visible: &mut [(usize, &mut MenuItem)],
-    match_count: usize,
 ) {
-    let mut to_activate = if let Some(match_offset) = match_offset {
-        if let Some((_, i)) = all_visible.get_mut(match_offset as usize % match_count) {
-            i
-        } else {
-            all_visible.first_mut().map(|(_, i)| i).unwrap()
-        }
+    let to_activate = if let Some(match_offset) = match_offset {
+        visible[match_offset as usize % visible.len()]
     } else if let Some(previous_active) = previous_active {
-        if let Some((_, i)) = all_visible.iter_mut().find(|(i, _)| *i == previous_active) {
-            i
-        } else {
-            all_visible.first_mut().map(|(_, i)| i).unwrap()
-        }
+        previous_active
     } else {
-        all_visible.first_mut().map(|(_, i)| i).unwrap()
+        visible[0]
     };
 
-    to_activate.state = ItemState::Active;
+    items[to_activate].state = ItemState::Active;
 }
 
-fn all_visible(items: &mut [MenuItem]) -> Vec<(usize, &mut MenuItem)> {
-    items
-        .iter_mut()
-        .enumerate()
-        .filter(|(_, i)| i.state != ItemState::Hidden)
-        .collect()
+fn load_frecency(path: &Path) -> HashMap<String, u32> {
+    match History::load(path) {
+        Ok(history) => history
+            .frecency(history::now())
+            .into_iter()
+            .map(|(value, frecency)| (value.to_string(), frecency))
+            .collect(),
+        Err(e) => {
+            eprintln!("Failed reading history {}: {}", path.display(), e);
+            HashMap::new()
+        }
+    }
+}
+
+/// Overwrites the text with zeros and clears it, so that a secret does not remain in
+/// freed memory.
+///
+/// Copies made while typing, e.g. by the text input widget, are not covered.
+pub fn zeroize(text: &mut String) {
+    // SAFETY: zero bytes are valid UTF-8
+    let bytes = unsafe { text.as_mut_vec() };
+    for byte in bytes.iter_mut() {
+        // volatile writes are not optimized away, although the text is cleared
+        unsafe { std::ptr::write_volatile(byte, 0) };
+    }
+    std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+    text.clear();
+}
+
+/// Removes the last word and the whitespace after it
+fn delete_word(input: &str) -> &str {
+    let trimmed = input.trim_end();
+    let word_start = trimmed
+        .char_indices()
+        .rev()
+        .find(|(_, c)| c.is_whitespace())
+        .map(|(i, c)| i + c.len_utf8())
+        .unwrap_or(0);
+    &trimmed[..word_start]
 }
 
 fn find_active(items: &mut [MenuItem]) -> Option<(usize, &MenuItem)> {
@@ -288,65 +568,40 @@ fn find_active(items: &mut [MenuItem]) -> Option<(usize, &MenuItem)> {
         .find(|i| i.1.state == ItemState::Active)
 }
 
-fn global_keyboard_handler(
-    event: iced_native::Event,
-    status: iced_native::event::Status,
-) -> Option<MainAction> {
-    fn on_key_pressed(
-        key_code: KeyCode,
-        modifiers: Modifiers,
-        _status: iced_native::event::Status,
-    ) -> Option<MainAction> {
-        use MainAction::*;
-        let action = match key_code {
-            KeyCode::Escape => modifiers.is_empty().then(|| Abort).unwrap_or(Focus),
-            KeyCode::Tab => {
-                if modifiers.is_empty() {
-                    NextTab
-                } else if modifiers == Modifiers::SHIFT {
-                    PreviousTab
-                } else {
-                    Focus
-                }
-            }
-            KeyCode::Right => {
-                if modifiers.is_empty() {
-                    Next
-                } else {
-                    Focus
-                }
-            }
-            KeyCode::Left => {
-                if modifiers.is_empty() {
-                    Previous
-                } else {
-                    Focus
-                }
-            }
-            _ => Focus,
-        };
-        Some(action)
-    }
-    match event {
-        iced_native::Event::Keyboard(Event::KeyPressed {
-            key_code,
-            modifiers,
-        }) => on_key_pressed(key_code, modifiers, status),
-
-        _ => Some(MainAction::Focus),
-    }
-}
-
 #[derive(Clone, Debug)]
 pub enum MainAction {
     Focus,
+    /// Resolved to an action by the [`Keymap`]
+    KeyPressed(KeyCode, Modifiers),
     Abort,
     Exit,
+    /// Accept the typed input, even when it does not match an option
+    AcceptInput,
+    /// Accept like [`MainAction::Exit`], but keep the menu open for more selections
+    AcceptContinue,
+    ToggleMark,
     Next,
     NextTab,
     Previous,
     PreviousTab,
+    /// Move the selection in the layout, see [`Move`]
+    Up,
+    Down,
+    Left,
+    Right,
+    First,
+    Last,
+    PageNext,
+    PagePrevious,
+    ClearInput,
+    DeleteWord,
     TextChanged(String),
+    /// Lines read from stdin
+    ItemsReceived(Vec<String>),
+    /// Stdin was closed, no more items will be received
+    InputClosed,
+    /// Reading stdin failed, [`MainAction::InputClosed`] follows
+    InputFailed(TMenuError),
 }
 
 impl Default for MainAction {
@@ -355,9 +610,190 @@ impl Default for MainAction {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ExitState {
     Continue,
+    /// Options were selected
     Exit,
+    /// Custom input was accepted
+    CustomInput,
     Abort,
+    /// The menu cannot be used, e.g. there are no options
+    Failed(TMenuError),
+}
+
+#[cfg(test)]
+mod test {
+    use crate::error::TMenuError;
+    use crate::scenario::{run, settings};
+    use crate::tmenu::{ExitState, MainAction, TMenu};
+    use crate::tmenu_settings::TMenuSettings;
+
+    #[test]
+    fn select_and_accept() {
+        let mut menu = TMenu::new(settings(&["foo", "bar", "foobar"]));
+        run(
+            &mut menu,
+            r#"type "fo"; visible "foo" "foobar"; active "foo"; next; active "foobar";
+               accept; expect "foobar"; exit "exit""#,
+        );
+    }
+
+    #[test]
+    fn abort_and_undefined_input() {
+        let mut menu = TMenu::new(settings(&["foo", "bar"]));
+        run(
+            &mut menu,
+            r#"type "x"; active; accept; expect; exit "abort""#,
+        );
+
+        let mut menu = TMenu::new(TMenuSettings {
+            allow_undefined: true,
+            ..settings(&["foo", "bar"])
+        });
+        run(
+            &mut menu,
+            r#"type "baz"; accept; expect "baz"; input ""; exit "custom-input""#,
+        );
+
+        let mut menu = TMenu::new(settings(&["foo"]));
+        run(&mut menu, r#"key "escape"; exit "abort""#);
+    }
+
+    #[test]
+    fn accept_and_continue() {
+        let mut menu = TMenu::new(TMenuSettings {
+            dmenu: true,
+            ..settings(&["foo", "bar"])
+        });
+        run(
+            &mut menu,
+            r#"key "ctrl+enter"; expect "foo"; exit "continue"; next; accept-continue;
+               expect "bar"; type "baz"; accept-continue; expect "baz"; input "baz";
+               key "shift+enter"; expect "baz"; exit "custom-input""#,
+        );
+
+        let mut menu = TMenu::new(TMenuSettings {
+            read_stdin: true,
+            allow_undefined: true,
+            ..settings(&[])
+        });
+        run(
+            &mut menu,
+            r#"close; exit "continue"; type "x"; accept; expect "x""#,
+        );
+    }
+
+    #[test]
+    fn accept_input_only_when_allowed() {
+        let mut menu = TMenu::new(settings(&["foo", "bar"]));
+        run(
+            &mut menu,
+            r#"type "baz"; accept-input; expect; exit "continue"; key "shift+enter"; expect;
+               exit "continue""#,
+        );
+
+        let mut menu = TMenu::new(TMenuSettings {
+            allow_undefined: true,
+            ..settings(&["foo"])
+        });
+        run(
+            &mut menu,
+            r#"accept-input; expect; exit "continue"; type "foo"; accept-input; expect "foo";
+               exit "custom-input""#,
+        );
+    }
+
+    #[test]
+    fn auto_accept_single_match() {
+        let mut menu = TMenu::new(TMenuSettings {
+            auto_accept: true,
+            ..settings(&["foo", "bar", "baz"])
+        });
+        run(
+            &mut menu,
+            r#"type "b"; exit "continue"; type "a"; exit "continue"; type "r"; expect "bar";
+               exit "exit""#,
+        );
+    }
+
+    #[test]
+    fn tab_completion() {
+        let mut menu = TMenu::new(settings(&["foo", "bar", "baz"]));
+        run(
+            &mut menu,
+            r#"next-complete; input "bar"; next-complete; input "baz";
+               previous-complete; input "bar"; type " x"; visible"#,
+        );
+    }
+
+    #[test]
+    fn multi_select_in_selection_order() {
+        let mut menu = TMenu::new(TMenuSettings {
+            multi_select: true,
+            ..settings(&["a", "b", "c"])
+        });
+        run(
+            &mut menu,
+            r#"last; toggle-mark; first; key "ctrl+space"; accept; expect "c" "a""#,
+        );
+    }
+
+    #[test]
+    fn items_from_stdin() {
+        let mut menu = TMenu::new(TMenuSettings {
+            read_stdin: true,
+            ..settings(&[])
+        });
+        run(
+            &mut menu,
+            r#"type "b"; receive "abc" "bcd"; visible "bcd" "abc"; receive "cde";
+               visible "bcd" "abc"; close; exit "continue"; accept; expect "bcd""#,
+        );
+
+        let mut menu = TMenu::new(TMenuSettings {
+            read_stdin: true,
+            ..settings(&[])
+        });
+        run(&mut menu, r#"close; exit "no-options""#);
+    }
+
+    #[test]
+    fn invalid_input_fails() {
+        let mut menu = TMenu::new(TMenuSettings {
+            read_stdin: true,
+            ..settings(&[])
+        });
+        let error = TMenuError::InvalidUtf8 { line: 2 };
+        run(&mut menu, r#"receive "a""#);
+        menu.update(MainAction::InputFailed(error.clone()));
+        run(&mut menu, r#"close; visible "a""#);
+        assert!(menu.should_exit());
+        assert_eq!(menu.exit_state(), ExitState::Failed(error));
+    }
+
+    #[test]
+    fn query_and_select() {
+        let mut menu = TMenu::new(TMenuSettings {
+            query: Some("o".to_string()),
+            select: Some("boo".to_string()),
+            ..settings(&["foo", "bar", "boo"])
+        });
+        assert!(menu.take_input_replaced());
+        run(&mut menu, r#"input "o"; visible "foo" "boo"; active "boo""#);
+    }
+
+    #[test]
+    fn password_is_accepted_and_cleared() {
+        let mut menu = TMenu::new(TMenuSettings {
+            password: true,
+            allow_undefined: true,
+            ..settings(&[])
+        });
+        run(
+            &mut menu,
+            r#"type "sec"; type "ret"; visible; accept; expect "secret"; input "";
+               exit "custom-input""#,
+        );
+    }
 }
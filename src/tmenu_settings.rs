@@ -1,10 +1,21 @@
-use std::cell::Cell;
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
+use crate::args::{self, ParsedArgs};
+use crate::config::{default_config_path, Config};
+use crate::error::TMenuError;
+use crate::filter::{CustomFilter, FilterFactory, MatchMode};
+use crate::geometry::{Dimension, Geometry, Placement};
+use crate::history::history_path_for_id;
+use crate::item_parser::{parse_columns, Column, InputFormat, ItemParser};
+use crate::keymap::{Keymap, ACTION_NAMES};
+use crate::layout::Layout;
 use crate::menu_item::MenuItem;
+use crate::monitor::MonitorSelection;
+use crate::output::{Output, OutputFormat};
+use crate::theme::{parse_color, Theme};
 use crate::tmenu::ExitState;
-use std::io::BufRead;
-use std::process::exit;
 
 #[derive(Debug, Clone)]
 pub struct TMenuSettings {
@@ -13,10 +24,80 @@ pub struct TMenuSettings {
     pub allow_undefined: bool,
     pub filter_by_prefix: bool,
     pub fuzzy: bool,
+    pub match_mode: MatchMode,
+    /// Replaces the filters of `match_mode`
+    pub custom_filter: Option<CustomFilter>,
+    pub multi_select: bool,
+    pub multi_order: MultiOrder,
+    /// Masks the input, which is printed as custom input
+    pub password: bool,
+    /// Label left of the input
+    pub prompt: Option<String>,
+    /// Shown in the empty input
+    pub placeholder: String,
+    /// Initial input
+    pub query: Option<String>,
+    /// Value or index of the initially active item
+    pub select: Option<String>,
     pub verbose: bool,
+    /// Invoked as dmenu: the typed input is accepted, when no option matches, and exits
+    /// with exit code 0
+    pub dmenu: bool,
     pub print_help: PrintHelp,
+    pub history: Option<PathBuf>,
+    pub input_format: InputFormat,
+    pub columns: Option<Vec<Column>>,
+    pub output_format: OutputFormat,
+    pub output: Output,
+    pub keymap: Keymap,
+    pub theme: Theme,
+    pub layout: Layout,
+    pub geometry: Geometry,
+    pub monitor: MonitorSelection,
+    pub backend: Backend,
+    /// Options are streamed from stdin, while the menu is shown
+    pub read_stdin: bool,
     pub available_options: Vec<MenuItem>,
-    pub exit_state: Rc<Cell<ExitState>>,
+    pub exit_state: Rc<RefCell<ExitState>>,
+}
+
+/// Order of the printed values, when multiple items are selected
+#[derive(Debug, Copy, Clone)]
+pub enum MultiOrder {
+    Selection,
+    Input,
+}
+
+impl MultiOrder {
+    pub fn parse(name: &str) -> Option<MultiOrder> {
+        match name {
+            "selection" => Some(MultiOrder::Selection),
+            "input" => Some(MultiOrder::Input),
+            _ => None,
+        }
+    }
+}
+
+/// How the menu window is created
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Backend {
+    /// The layer shell on Wayland compositors supporting it, winit otherwise
+    Auto,
+    /// A regular window, positioned by the window manager
+    Winit,
+    /// A wlr-layer-shell overlay surface, for wlroots based Wayland compositors
+    LayerShell,
+}
+
+impl Backend {
+    pub fn parse(name: &str) -> Option<Backend> {
+        match name {
+            "auto" => Some(Backend::Auto),
+            "winit" => Some(Backend::Winit),
+            "layer-shell" => Some(Backend::LayerShell),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -24,6 +105,9 @@ pub enum PrintHelp {
     No,
     Short,
     Long,
+    /// The completion script of a shell
+    Completions(String),
+    Version,
 }
 
 impl Default for TMenuSettings {
@@ -34,44 +118,106 @@ impl Default for TMenuSettings {
             allow_undefined: false,
             filter_by_prefix: false,
             fuzzy: false,
+            match_mode: MatchMode::Contains,
+            custom_filter: None,
+            multi_select: false,
+            multi_order: MultiOrder::Selection,
+            password: false,
+            prompt: None,
+            placeholder: "option".to_string(),
+            query: None,
+            select: None,
             verbose: false,
+            dmenu: false,
             print_help: PrintHelp::No,
+            history: None,
+            input_format: InputFormat::Plain,
+            columns: None,
+            output_format: OutputFormat::Plain,
+            output: Output::Stdout,
+            keymap: Keymap::default(),
+            theme: Theme::default(),
+            layout: Layout::Bar,
+            geometry: Geometry::default(),
+            monitor: MonitorSelection::Primary,
+            backend: Backend::Auto,
+            read_stdin: false,
             available_options: vec![],
-            exit_state: Rc::new(Cell::new(ExitState::Continue)),
+            exit_state: Rc::new(RefCell::new(ExitState::Continue)),
         }
     }
 }
 
 impl TMenuSettings {
-    pub fn from_args(args: Vec<String>) -> Self {
-        let stdin = std::io::stdin();
-        Self::from_args_and_input(args, || stdin.lock())
+    pub fn builder() -> TMenuSettingsBuilder {
+        TMenuSettingsBuilder::default()
     }
 
-    pub fn from_args_and_input<LINES>(args: Vec<String>, input: impl FnOnce() -> LINES) -> Self
-    where
-        LINES: BufRead,
-    {
-        let mut settings = Self::default();
-        let read_stdin = parse_args(args, &mut settings);
+    /// Settings from the config file and the command line arguments, starting with the
+    /// program name
+    ///
+    /// Options missing on the command line are taken from `TMENU_*` environment variables.
+    pub fn from_args(args: Vec<String>) -> Result<Self, TMenuError> {
+        Self::from_args_and_env(args, |name| std::env::var(name).ok())
+    }
 
-        if read_stdin {
-            read_options_stdin(&mut settings, input);
+    fn from_args_and_env(
+        args: Vec<String>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, TMenuError> {
+        let program = args
+            .first()
+            .and_then(|path| Path::new(path).file_name())
+            .map_or("tmenu".to_string(), |name| {
+                name.to_string_lossy().into_owned()
+            });
+        let parsed = args::parse(&program, args.get(1..).unwrap_or_default(), env)?;
+        let mut settings = Self::default();
+        apply_config(&parsed, &mut settings)?;
+        settings.read_stdin = apply_args(parsed, &program, &mut settings)?;
+        if settings.password {
+            settings.apply_password_mode();
         }
+        Ok(settings)
+    }
 
-        if let PrintHelp::No = settings.print_help {
-            if settings.available_options.is_empty() {
-                eprintln!("No options available");
-                exit(2);
-            }
+    /// There is nothing to choose from and nothing to type
+    pub fn missing_options(&self) -> bool {
+        !self.password && !self.read_stdin && self.available_options.is_empty()
+    }
+
+    /// The typed text is the only result: no options are shown and the input is neither
+    /// remembered nor logged
+    fn apply_password_mode(&mut self) {
+        if !self.available_options.is_empty() {
+            eprintln!("Options are ignored with --password");
         }
+        self.available_options.clear();
+        self.read_stdin = false;
+        self.allow_undefined = true;
+        self.auto_accept = false;
+        self.multi_select = false;
+        self.history = None;
+        self.verbose = false;
+    }
 
-        settings
+    /// Logical height of the menu content, the default window height
+    pub fn content_height(&self) -> u32 {
+        self.layout.rows() * self.theme.item_height as u32
     }
 
-    pub(crate) fn maybe_print_help(&self) -> bool {
-        match self.print_help {
+    /// Prints the help or the shell completions, when requested
+    pub fn maybe_print_help(&self) -> bool {
+        match &self.print_help {
             PrintHelp::No => false,
+            PrintHelp::Completions(script) => {
+                print!("{}", script);
+                true
+            }
+            PrintHelp::Version => {
+                println!("tmenu {}", env!("CARGO_PKG_VERSION"));
+                true
+            }
             _ => {
                 print_help(self);
                 true
@@ -80,66 +226,341 @@ impl TMenuSettings {
     }
 }
 
-fn parse_args(args: Vec<String>, state: &mut TMenuSettings) -> bool {
-    let args_ref: Vec<&str> = args.iter().map(String::as_str).collect();
-    let mut remaining = &args_ref[1..];
+/// Builds [`TMenuSettings`] in code, for applications embedding the menu.
+///
+/// Starts with the defaults, no config file is read.
+#[derive(Debug, Default)]
+pub struct TMenuSettingsBuilder {
+    settings: TMenuSettings,
+}
 
-    let mut read_stdin = true;
+impl TMenuSettingsBuilder {
+    /// Adds an option, parsed like a line of plain input
+    pub fn option(mut self, text: &str) -> Self {
+        let index = self.settings.available_options.len();
+        self.settings
+            .available_options
+            .push(MenuItem::create(text, index));
+        self
+    }
+
+    /// Adds prepared options, numbered after the options added before
+    pub fn items(mut self, items: Vec<MenuItem>) -> Self {
+        let options = &mut self.settings.available_options;
+        for mut item in items {
+            item.index = options.len();
+            options.push(item);
+        }
+        self
+    }
+
+    /// Streams more options from stdin, while the menu is shown
+    pub fn read_stdin(mut self, read_stdin: bool) -> Self {
+        self.settings.read_stdin = read_stdin;
+        self
+    }
+
+    pub fn auto_accept(mut self, auto_accept: bool) -> Self {
+        self.settings.auto_accept = auto_accept;
+        self
+    }
+
+    pub fn case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.settings.case_insensitive = case_insensitive;
+        self
+    }
+
+    pub fn match_prefix(mut self, match_prefix: bool) -> Self {
+        self.settings.filter_by_prefix = match_prefix;
+        self
+    }
+
+    pub fn fuzzy(mut self, fuzzy: bool) -> Self {
+        self.settings.fuzzy = fuzzy;
+        self
+    }
+
+    pub fn match_mode(mut self, match_mode: MatchMode) -> Self {
+        self.settings.match_mode = match_mode;
+        self
+    }
+
+    /// Filters the items with a custom [`FilterFactory`], created from the final settings
+    pub fn filter(
+        mut self,
+        create: impl Fn(&TMenuSettings) -> Box<dyn FilterFactory> + 'static,
+    ) -> Self {
+        self.settings.custom_filter = Some(CustomFilter(Rc::new(create)));
+        self
+    }
+
+    pub fn allow_undefined(mut self, allow_undefined: bool) -> Self {
+        self.settings.allow_undefined = allow_undefined;
+        self
+    }
+
+    pub fn multi_select(mut self, multi_select: bool) -> Self {
+        self.settings.multi_select = multi_select;
+        self
+    }
+
+    pub fn multi_order(mut self, multi_order: MultiOrder) -> Self {
+        self.settings.multi_order = multi_order;
+        self
+    }
+
+    /// Masks the input and ignores all options, see `--password`
+    pub fn password(mut self, password: bool) -> Self {
+        self.settings.password = password;
+        self
+    }
+
+    pub fn prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.settings.prompt = Some(prompt.into());
+        self
+    }
+
+    pub fn placeholder(mut self, placeholder: impl Into<String>) -> Self {
+        self.settings.placeholder = placeholder.into();
+        self
+    }
+
+    pub fn query(mut self, query: impl Into<String>) -> Self {
+        self.settings.query = Some(query.into());
+        self
+    }
+
+    /// Value or index of the initially active item
+    pub fn select(mut self, select: impl Into<String>) -> Self {
+        self.settings.select = Some(select.into());
+        self
+    }
+
+    pub fn history(mut self, path: impl Into<PathBuf>) -> Self {
+        self.settings.history = Some(path.into());
+        self
+    }
+
+    pub fn output_format(mut self, output_format: OutputFormat) -> Self {
+        self.settings.output_format = output_format;
+        self
+    }
+
+    pub fn keymap(mut self, keymap: Keymap) -> Self {
+        self.settings.keymap = keymap;
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.settings.theme = theme;
+        self
+    }
+
+    pub fn layout(mut self, layout: Layout) -> Self {
+        self.settings.layout = layout;
+        self
+    }
+
+    pub fn geometry(mut self, geometry: Geometry) -> Self {
+        self.settings.geometry = geometry;
+        self
+    }
+
+    pub fn monitor(mut self, monitor: MonitorSelection) -> Self {
+        self.settings.monitor = monitor;
+        self
+    }
+
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.settings.backend = backend;
+        self
+    }
+
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.settings.verbose = verbose;
+        self
+    }
+
+    pub fn build(mut self) -> TMenuSettings {
+        if self.settings.password {
+            self.settings.apply_password_mode();
+        }
+        self.settings
+    }
+}
+
+/// Applies the config file options, so that command line options override them.
+///
+/// `--config FILE`, `--no-config` and `--profile NAME` are applied first.
+/// A missing default config file is not an error, a missing profile is.
+fn apply_config(args: &ParsedArgs, state: &mut TMenuSettings) -> Result<(), TMenuError> {
+    let mut config_file = default_config_path();
+    let mut explicit_file = false;
+    let mut profile = None;
 
-    loop {
-        match remaining {
-            ["-a" | "--auto-accept", r @ ..] => {
-                state.auto_accept = true;
-                remaining = r;
+    for (spec, value) in &args.options {
+        match (spec.name, value.as_deref()) {
+            ("config", Some(file)) => {
+                config_file = Some(PathBuf::from(file));
+                explicit_file = true;
             }
-            ["-i" | "--case-insensitive", r @ ..] => {
-                state.case_insensitive = true;
-                remaining = r;
+            ("no-config", _) => config_file = None,
+            ("profile", Some(name)) => profile = Some(name),
+            _ => {}
+        }
+    }
+
+    let config = match &config_file {
+        Some(path) => match Config::load(path)? {
+            None if explicit_file => {
+                return Err(TMenuError::Io(format!(
+                    "Config file not found: {}",
+                    path.display()
+                )))
             }
-            ["-p" | "--match_prefix", r @ ..] => {
-                state.filter_by_prefix = true;
-                remaining = r;
+            config => config,
+        },
+        None => None,
+    };
+    match (config, profile) {
+        (Some(config), profile) => config.options(profile)?.apply(state),
+        (None, Some(profile)) => Err(format!("Unknown profile: {}", profile).into()),
+        (None, None) => Ok(()),
+    }
+}
+
+/// Applies the parsed options, returns whether options are read from stdin
+fn apply_args(
+    args: ParsedArgs,
+    program: &str,
+    state: &mut TMenuSettings,
+) -> Result<bool, TMenuError> {
+    let mut read_stdin = true;
+    let mut key_files = Vec::new();
+    let mut key_bindings = Vec::new();
+    let mut input_format = None;
+    state.dmenu = args.dmenu;
+
+    for (spec, value) in args.options {
+        let value = value.unwrap_or_default();
+        match spec.name {
+            "auto-accept" => state.auto_accept = true,
+            "case-insensitive" => state.case_insensitive = true,
+            "match-prefix" => state.filter_by_prefix = true,
+            "match" => {
+                state.match_mode = MatchMode::parse(&value)
+                    .ok_or_else(|| format!("Unknown match mode: {}", value))?;
             }
-            ["-f" | "--fuzzy", r @ ..] => {
-                state.fuzzy = true;
-                remaining = r;
+            "fuzzy" => state.fuzzy = true,
+            "allow-undefined" => state.allow_undefined = true,
+            "password" => state.password = true,
+            "prompt" => state.prompt = Some(value),
+            "placeholder" => state.placeholder = value,
+            "query" => state.query = Some(value),
+            "select" => state.select = Some(value),
+            "multi" => state.multi_select = true,
+            "multi-order" => {
+                state.multi_order = MultiOrder::parse(&value)
+                    .ok_or_else(|| format!("Unknown multi select order: {}", value))?;
             }
-            ["-u" | "--allow-undefined", r @ ..] => {
-                state.allow_undefined = true;
-                remaining = r;
+            "history" => state.history = Some(PathBuf::from(value)),
+            "id" => {
+                state.history = Some(
+                    history_path_for_id(&value)
+                        .ok_or_else(|| format!("Invalid menu id: {}", value))?,
+                );
             }
-            ["--", options @ ..] => {
-                read_stdin = false;
-                options.iter().enumerate().for_each(|(index, s)| {
-                    state.available_options.push(MenuItem::create(s, index))
-                });
-                break;
+            "input-format" => {
+                input_format = Some(
+                    InputFormat::parse(&value)
+                        .ok_or_else(|| format!("Unknown input format: {}", value))?,
+                );
+            }
+            "output-format" => {
+                state.output_format = OutputFormat::parse(&value)
+                    .ok_or_else(|| format!("Unknown output format: {}", value))?;
             }
-            ["--verbose", r @ ..] => {
-                state.verbose = true;
-                remaining = r;
+            // keeps the bindings of the config file
+            "keys" => state.keymap.set_preset(&value)?,
+            "keymap" => key_files.push(value),
+            "bind" => key_bindings.push(value),
+            "nb" => state.theme.background = parse_color(&value)?,
+            "nf" => state.theme.foreground = parse_color(&value)?,
+            "sb" => state.theme.selected_background = parse_color(&value)?,
+            "sf" => state.theme.selected_foreground = parse_color(&value)?,
+            "fn" => state.theme.set_font(&value)?,
+            "lines" => state.layout = Layout::parse_lines(&value)?,
+            "grid" => state.layout = Layout::parse_grid(&value)?,
+            "width" => state.geometry.width = Some(Dimension::parse(&value)?),
+            "height" => state.geometry.height = Some(Dimension::parse(&value)?),
+            "max-width" => state.geometry.max_width = Some(Dimension::parse(&value)?),
+            "x" => state.geometry.x = Dimension::parse(&value)?,
+            "y" => state.geometry.y = Dimension::parse(&value)?,
+            "monitor" => state.monitor = MonitorSelection::parse(&value),
+            "backend" => {
+                state.backend =
+                    Backend::parse(&value).ok_or_else(|| format!("Invalid backend: {}", value))?;
             }
-            ["-h", r @ ..] => {
-                state.print_help = PrintHelp::Short;
+            "bottom" => state.geometry.placement = Placement::Bottom,
+            "center" => state.geometry.placement = Placement::Center,
+            "columns" => state.columns = Some(parse_columns(&value)?),
+            // already applied by apply_config
+            "config" | "no-config" | "profile" => {}
+            // read by args::parse, as it changes the meaning of short options
+            "dmenu" => {}
+            "verbose" => state.verbose = true,
+            "completions" => {
+                let script = args::completions(&value, program)
+                    .ok_or_else(|| format!("Unknown shell: {}", value))?;
+                state.print_help = PrintHelp::Completions(script);
                 read_stdin = false;
-                remaining = r;
             }
-            ["--help", r @ ..] => {
-                state.print_help = PrintHelp::Long;
+            "version" => {
+                state.print_help = PrintHelp::Version;
                 read_stdin = false;
-                remaining = r;
             }
-            [option, ..] => {
-                eprintln!("Unknown option: {}", option);
-                exit(-1);
+            "help" => {
+                state.print_help = if args.long_help {
+                    PrintHelp::Long
+                } else {
+                    PrintHelp::Short
+                };
+                read_stdin = false;
             }
-            [] => {
-                break;
+            // an option of args::OPTIONS without an arm here
+            name => return Err(format!("Unsupported option: --{}", name).into()),
+        }
+    }
+
+    // dmenu scripts pass arbitrary lines, like URLs with '=' or titles starting with '('
+    match input_format {
+        Some(format) => state.input_format = format,
+        None if state.dmenu => state.input_format = InputFormat::Verbatim,
+        None => {}
+    }
+
+    if let Some(items) = args.items {
+        read_stdin = false;
+        let mut parser = ItemParser::new(state.input_format, state.columns.clone());
+        for item in items {
+            let index = state.available_options.len();
+            match parser.parse_line(&item, index) {
+                Ok(Some(item)) => state.available_options.push(item),
+                Ok(None) => {}
+                Err(e) => return Err(format!("Invalid item '{}': {}", item, e).into()),
             }
         }
     }
 
-    read_stdin
+    // bindings override the preset, regardless of the argument order
+    for file in key_files {
+        state.keymap.load(Path::new(&file))?;
+    }
+    for binding in key_bindings {
+        state.keymap.bind_spec(&binding)?;
+    }
+
+    Ok(read_stdin)
 }
 
 pub(crate) fn print_help(_settings: &TMenuSettings) {
@@ -149,8 +570,12 @@ pub(crate) fn print_help(_settings: &TMenuSettings) {
 Displays a basic menu controlled by keyboard.
 When an item is selected, it is printed on stdout and the program
 returns exit code 0, when stopped with the ESC key, nothing is printed
-and it exits with exit code 1.
+and it exits with exit code 1. Exit code 2 means no options were
+passed, exit code 3 that custom input was accepted (--allow-undefined).
+Failures exit with 4 for invalid arguments, 5 for I/O errors, 6 for
+input, that is not valid UTF-8, and 7, when no window could be shown.
 Menu items are read from stdin or passed as arguments.
+Items read from stdin are shown while they arrive.
 
 The main difference to the suckless tool dmenu, tmenu is designed
 to also work on wayland (also Mac and Windows). Accepts mouse input
@@ -172,9 +597,49 @@ Options:
     -a, --auto-accept       Auto accept option when single option matches
     -i, --case-insensitive  Match options case insensitive
     -p, --match-prefix      Match options using starts-with matcher
+    -f, --fuzzy             Fuzzy match options and sort them by relevance
+    --match MODE            Match options: contains (default), exact, words or regex
     -u, --allow-undefined   Allow users to type custom options
-    --verbose               More verbose lot output on stderr
+    --password              Masked input, prints the typed text
+    --prompt TEXT           Label shown left of the input
+    --placeholder TEXT      Text shown in the empty input, default: option
+    --query TEXT            Initial input, that filters the options
+    --select VALUE|INDEX    Initially active option
+    --multi                 Select multiple options with Ctrl+Space
+    --multi-order ORDER     Print selected options in 'selection' or 'input' order
+    --input-format FORMAT   Item format: plain (default), json, tsv or verbatim
+    --columns COLUMNS       TSV columns, e.g. value,text,hint
+    --output-format FORMAT  Result format: plain (default), json, index or nul
+    --keys PRESET           Key bindings: default, emacs or vim
+    --keymap FILE           Load key bindings from FILE
+    --bind KEY=ACTION       Bind a key, e.g. --bind ctrl+n=next
+    --lines N               Show options in a vertical list of N lines
+    --grid COLSxROWS        Show options in a grid, e.g. --grid 8x4
+    -w, --width SIZE        Window width in pixels or percent, e.g. 800 or 50%
+    --height SIZE           Window height, defaults to the height of the options
+    --max-width SIZE        Limits the window width
+    --x SIZE, --y SIZE      Offset of the window
+    --bottom                Place the menu at the bottom of the screen
+    --monitor MONITOR       Monitor index or name, focused or pointer
+    --center                Place the menu in the center of the screen
+    --backend BACKEND       Window backend: auto, winit or layer-shell
+    -nb, -nf COLOR          Normal background and foreground color, like #222222
+    -sb, -sf COLOR          Selected item background and foreground color
+    -fn FONT                Font family or file, with optional size: FONT:size=14
+    --history FILE          Rank frequently selected options first
+    --id ID                 Use the history of the menu ID
+    --config FILE           Read options from FILE instead of the default config
+    --no-config             Ignore the config file
+    --profile NAME          Apply the options of profile NAME from the config file
+    -v, --verbose           More verbose lot output on stderr
+    --dmenu                 Accept dmenu options, also when invoked as dmenu
+    --completions SHELL     Print the completion script for bash, zsh or fish
+    --version               Print the version
     -h, --help              print help message. --help for more details
+
+Short flags can be combined like -ai, values follow the option or are
+attached like --width=50% and -w50%. Options are also read from TMENU_*
+environment variables, like TMENU_CASE_INSENSITIVE=1 or TMENU_PROMPT=Run.
     "#
     } else {
         r#"
@@ -195,37 +660,399 @@ Options:
     -p, --match-prefix
         Keyboard input matches only the start of the options
 
+    -f, --fuzzy
+        Keyboard input matches options, that contain all typed characters
+        in the same order. Matching options are sorted by relevance,
+        matches at word starts, camelCase humps and consecutive
+        characters rank higher. Takes precedence over --match-prefix.
+
+    --match MODE
+        How keyboard input matches options, all modes respect
+        --case-insensitive:
+        contains: options containing the input, the default. Refined by
+                  --match-prefix and --fuzzy
+        exact:    options equal to the input
+        words:    options containing all words of the input, in any order
+        regex:    options matching the input as regular expression. While
+                  the expression is invalid, all options are shown and the
+                  error is shown next to the input
+
     -u, --allow-undefined
         Allow users to type custom options.
         This excludes the --auto-accept.
 
-    --verbose
+    --password
+        Masks the input with bullets and prints the typed text on Enter,
+        like a pinentry program. Options are ignored and neither read from
+        stdin nor required. History and --verbose are disabled, and the
+        input is overwritten in memory after use.
+
+    --prompt TEXT
+        A label shown left of the input, like "Switch to branch:". It is
+        drawn in the prompt colors of the theme, which default to the
+        selected item colors.
+
+    --placeholder TEXT
+        Text shown in the empty input, "option" by default.
+
+    --query TEXT
+        Starts with TEXT typed into the input, the options are filtered
+        immediately. --auto-accept applies, when a single option matches.
+
+    --select VALUE|INDEX
+        Activates the option with the value VALUE, or the option at
+        position INDEX in input order, starting with 0. Options read from
+        stdin are selected once they arrive.
+
+    --multi
+        Allows selecting multiple options.
+        Ctrl+Space marks or unmarks the active option. On Enter all marked
+        options are printed, one per line. When no option is marked, the
+        active option is printed.
+        Disables --auto-accept.
+
+    --multi-order ORDER
+        Order of the printed options in --multi mode.
+        'selection' (default): the order in which options were marked
+        'input': the order in which options were passed to tmenu
+
+    --input-format FORMAT
+        Syntax of the passed items.
+        plain: [(MNEMONIC)] [KEY=]VALUE, the default
+        json:  one JSON object per line, with the optional string fields
+               text, value, mnemonic, icon and hint.
+               Either text or value is required.
+        tsv:   tab separated columns, the first line names the columns,
+               unless --columns is given. \t, \n and \\ are unescaped.
+        verbatim: each line as it is, the default with --dmenu.
+
+    --columns COLUMNS
+        Comma separated item fields of the TSV columns: text, value,
+        mnemonic, icon or hint. _ skips a column.
+
+    --output-format FORMAT
+        How selected options are printed.
+        plain: the option value (key), one per line, the default
+        json:  one JSON object per line, with the fields value, text,
+               index, mnemonic, typed_input and undefined. undefined is
+               true for custom input (--allow-undefined).
+        index: the position of the option in the input, one per line,
+               -1 for custom input
+        nul:   the option value, terminated by a NUL character
+
+    --keys PRESET
+        Built in key bindings. Bindings of the config file, --keymap and
+        --bind override the preset, regardless of the order.
+        default: Enter accepts, Shift+Enter accepts the typed input
+                 (--allow-undefined, --dmenu and --password only),
+                 Ctrl+Enter accepts and keeps the menu open,
+                 Escape aborts, Left/Right and Up/Down select options,
+                 Tab/Shift+Tab select and complete options, PageUp/PageDown,
+                 Ctrl+Home/Ctrl+End, Ctrl+Space marks (--multi)
+        emacs:   default, Ctrl+N/Ctrl+P, Ctrl+G, Ctrl+J, Ctrl+U, Ctrl+W
+        vim:     default, Ctrl+J/Ctrl+K, Ctrl+N/Ctrl+P, Ctrl+F/Ctrl+B,
+                 Ctrl+[, Ctrl+U, Ctrl+W
+
+    --keymap FILE
+        Load key bindings from FILE, one KEY = ACTION per line.
+        Lines starting with # are comments.
+
+    --bind KEY=ACTION
+        Bind KEY to ACTION, applied after --keys and --keymap.
+        KEY:    modifiers and a key, joined by +, like ctrl+shift+n.
+                Modifiers: ctrl, shift, alt, logo
+                Keys: a-z, 0-9, f1-f12, escape, enter, tab, space,
+                backspace, delete, insert, home, end, page_up,
+                page_down, up, down, left, right, [, ], comma, period,
+                minus, slash, semicolon
+        ACTION: {actions}
+
+    --lines N
+        Show N options in a vertical list below the input, instead of
+        a single line. Up/Down select options, the list scrolls with
+        the selection. 0 is the single line menu.
+
+    --grid COLSxROWS
+        Show options in a grid of COLS columns and ROWS rows below the
+        input, for short options like emojis or application names.
+        The arrow keys move the selection in both dimensions.
+
+    -w SIZE, --width SIZE
+        Width of the window, in logical pixels like 800 or in percent of
+        the screen width like 50%. Defaults to the screen width, or 50%
+        with --center.
+
+    --height SIZE
+        Height of the window, defaults to the height of the input and the
+        options of --lines or --grid.
+
+    --max-width SIZE
+        Maximum width of the window, useful with percent widths and
+        --center.
+
+    --x SIZE, --y SIZE
+        Offset of the window from the left and the top edge of the screen,
+        from the bottom edge with --bottom and from the centered position
+        with --center.
+
+    --bottom
+        Place the menu at the bottom of the screen.
+
+    --monitor MONITOR
+        The monitor, that shows the menu:
+        primary: the primary monitor, the default
+        INDEX:   position in the list of monitors, starting with 0
+        NAME:    output name like DP-1
+        focused: the monitor with the focused window, supported on sway,
+                 Hyprland and i3
+        pointer: the monitor under the mouse pointer, supported on X11
+                 with xdotool and on Hyprland, otherwise like focused
+        --verbose lists the detected monitors.
+
+    --center
+        Place the menu in the center of the screen.
+
+    --backend BACKEND
+        How the menu window is created:
+        auto:        layer-shell, when running on a Wayland compositor, that
+                     supports it, otherwise winit. The default
+        winit:       a regular window, Wayland compositors ignore its position
+        layer-shell: an overlay surface of the wlr-layer-shell protocol, that
+                     takes the keyboard focus, supported by sway, Hyprland
+                     and other wlroots based compositors
+
+    -nb COLOR, -nf COLOR
+        Background and foreground color of the menu, as #rgb, #rrggbb or
+        #rrggbbaa, like dmenu.
+
+    -sb COLOR, -sf COLOR
+        Background and foreground color of the selected option.
+
+    -fn FONT
+        Font family, resolved with fc-match, or path of a font file.
+        The size is appended like dmenu fonts: Monospace:size=14
+        More theme settings, like the mnemonic color or the separator,
+        are read from the [theme] table of the config file.
+
+    --history FILE
+        Remember selected options in FILE.
+        Options selected often and recently (frecency) are shown first,
+        when nothing is typed, and rank above equally good matches.
+
+    --id ID
+        Same as --history $XDG_STATE_HOME/tmenu/ID.
+        Scripts should use a unique ID per menu.
+
+    --config FILE
+        Read options from the TOML file FILE, instead of
+        $XDG_CONFIG_HOME/tmenu/config.toml (default ~/.config/tmenu/...).
+        Keys are the long option names without dashes, like
+        case-insensitive = true or output-format = "json". Key bindings
+        are set in a [bind] table, like "ctrl+n" = "next".
+        Command line options override the config file.
+
+    --no-config
+        Ignore the config file.
+
+    --profile NAME
+        Apply the options of the [profiles.NAME] table of the config file,
+        on top of its top level options.
+
+    -v, --verbose
         More verbose output on stderr.
         For debugging only.
 
+    --dmenu
+        dmenu compatibility, also enabled when tmenu is invoked as dmenu,
+        e.g. through a symlink. Short options are read as dmenu options:
+        -b bottom, -i case insensitive, -l LINES, -p PROMPT, -m MONITOR,
+        -fn FONT, -nb, -nf, -sb, -sf COLOR, -v version. -f has no effect,
+        -w WINDOWID is ignored with a warning, as embedding is not supported.
+        Enter accepts the typed text, when no option matches, and the
+        exit code is 0 for typed text as well. Lines are read verbatim,
+        unless --input-format is given. Long options still work.
+
+    --completions SHELL
+        Print the completion script for bash, zsh or fish, e.g.
+        tmenu --completions fish > ~/.config/fish/completions/tmenu.fish
+
+    --version
+        Print the version.
+
     -h, --help
         print help message. --help for more details
+
+Syntax:
+    Short flags can be combined like -ai. Values follow the option as
+    next argument, or are attached like --width=50% or -w50%. The dmenu
+    style options -nb, -nf, -sb, -sf and -fn take the next argument.
+
+Environment:
+    Options missing on the command line are read from environment
+    variables, named TMENU_ and the long option name in upper case, with
+    _ instead of -, like TMENU_PROMPT="Run" or TMENU_LINES=10. Flags are
+    enabled by 1, true, yes or on. Environment variables override the
+    config file, command line options override both.
     "#
     };
 
+    let actions: Vec<String> = ACTION_NAMES.chunks(5).map(|c| c.join(", ")).collect();
+    let msg = msg.replace("{actions}", &actions.join(",\n                "));
+
     println!("tmenu {}\n{}\n{}{}", version, author, description, msg)
 }
 
-fn read_options_stdin<LINES>(state: &mut TMenuSettings, input: impl FnOnce() -> LINES)
-where
-    LINES: BufRead,
-{
-    let std_in = input();
-    let mut lines = std_in.lines();
-    let mut index = 0;
-    while let Some(line) = lines.next() {
-        match line {
-            Ok(line) => state.available_options.push(MenuItem::create(&line, index)),
-            Err(e) => {
-                eprintln!("Failed reading stdin: {:?}", e);
-                exit(-1);
+#[cfg(test)]
+mod test {
+    use crate::args::{ParsedArgs, OPTIONS};
+    use crate::error::TMenuError;
+    use crate::filter::MatchMode;
+    use crate::geometry::Dimension;
+    use crate::item_parser::InputFormat;
+    use crate::layout::Layout;
+    use crate::menu_item::MenuItem;
+    use crate::monitor::MonitorSelection;
+    use crate::scenario::run;
+    use crate::tmenu::TMenu;
+    use crate::tmenu_settings::{apply_args, PrintHelp, TMenuSettings};
+
+    fn args(args: &[&str]) -> Vec<String> {
+        ["tmenu", "--no-config"]
+            .iter()
+            .chain(args)
+            .map(|a| a.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn every_option_is_applied() {
+        for spec in OPTIONS {
+            let args = ParsedArgs {
+                options: vec![(spec, spec.takes_value().then(|| "x".to_string()))],
+                ..ParsedArgs::default()
+            };
+            let mut settings = TMenuSettings::default();
+            // values may be invalid, but every option has to be known
+            if let Err(TMenuError::Arguments(message)) = apply_args(args, "tmenu", &mut settings) {
+                assert!(!message.starts_with("Unsupported option"), "{}", message);
             }
         }
-        index += 1;
+    }
+
+    #[test]
+    fn invalid_arguments_are_errors() {
+        let settings = TMenuSettings::from_args(args(&["--match", "words", "--", "a"])).unwrap();
+        assert_eq!(settings.match_mode, MatchMode::Words);
+        assert!(!settings.read_stdin);
+        assert_eq!(settings.available_options.len(), 1);
+
+        let error = TMenuSettings::from_args(args(&["--match", "glob"])).unwrap_err();
+        let message = "Unknown match mode: glob".to_string();
+        assert_eq!(error, TMenuError::Arguments(message));
+        let error = TMenuSettings::from_args(args(&["--frobnicate"])).unwrap_err();
+        let message = "Unknown option: --frobnicate".to_string();
+        assert_eq!(error, TMenuError::Arguments(message));
+
+        let error = TMenuSettings::from_args(args(&["--keymap", "/nonexistent/keymap"]));
+        assert!(matches!(error, Err(TMenuError::Io(_))));
+    }
+
+    #[test]
+    fn arguments_and_environment() {
+        let env = |name: &str| match name {
+            "TMENU_LINES" => Some("5".to_string()),
+            "TMENU_PROMPT" => Some("Run".to_string()),
+            _ => None,
+        };
+        let arguments = args(&["-aiw", "50%", "--prompt=Open", "--match-prefix", "--", "a"]);
+        let settings = TMenuSettings::from_args_and_env(arguments, env).unwrap();
+        assert!(settings.auto_accept && settings.case_insensitive && settings.filter_by_prefix);
+        assert_eq!(settings.geometry.width, Some(Dimension::Percent(50.0)));
+        assert_eq!(settings.prompt.as_deref(), Some("Open"));
+        assert_eq!(settings.layout, Layout::with_lines(5));
+
+        let settings = TMenuSettings::from_args_and_env(args(&["--completions", "fish"]), env);
+        assert!(matches!(
+            settings.unwrap().print_help,
+            PrintHelp::Completions(_)
+        ));
+    }
+
+    #[test]
+    fn dmenu_mode() {
+        let args = [
+            "dmenu",
+            "--no-config",
+            "-i",
+            "-l",
+            "10",
+            "-p",
+            "Run:",
+            "-m",
+            "1",
+        ]
+        .map(String::from);
+        let settings = TMenuSettings::from_args_and_env(args.to_vec(), |_| None).unwrap();
+        assert!(settings.dmenu && settings.case_insensitive);
+        assert!(settings.read_stdin);
+        assert!(!settings.filter_by_prefix);
+        assert_eq!(settings.prompt.as_deref(), Some("Run:"));
+        assert_eq!(settings.layout, Layout::with_lines(10));
+        assert_eq!(settings.monitor, MonitorSelection::Index(1));
+
+        let args = ["dmenu", "--no-config", "-v"].map(String::from);
+        let settings = TMenuSettings::from_args_and_env(args.to_vec(), |_| None).unwrap();
+        assert!(matches!(settings.print_help, PrintHelp::Version));
+        assert!(!settings.read_stdin);
+    }
+
+    #[test]
+    fn dmenu_lines_are_verbatim() {
+        let args = ["dmenu", "--no-config"].map(String::from);
+        let settings = TMenuSettings::from_args_and_env(args.to_vec(), |_| None).unwrap();
+        assert_eq!(settings.input_format, InputFormat::Verbatim);
+        let mut menu = TMenu::new(settings);
+        run(
+            &mut menu,
+            r#"receive "https://x.org/?a=b" "PATH=/bin" "(foo) bar"; close;
+               visible "https://x.org/?a=b" "PATH=/bin" "(foo) bar";
+               type "(f"; visible "(foo) bar"; accept; expect "(foo) bar"; exit "exit""#,
+        );
+
+        let args = [
+            "dmenu",
+            "--no-config",
+            "--input-format",
+            "plain",
+            "--",
+            "a=b",
+        ];
+        let settings =
+            TMenuSettings::from_args_and_env(args.map(String::from).to_vec(), |_| None).unwrap();
+        assert_eq!(settings.available_options[0].value(), "a");
+    }
+
+    #[test]
+    fn build_settings() {
+        let settings = TMenuSettings::builder()
+            .option("a")
+            .option("(b) key=text")
+            .items(vec![MenuItem::create("c", 0)])
+            .prompt("Run")
+            .case_insensitive(true)
+            .build();
+        assert_eq!(settings.prompt.as_deref(), Some("Run"));
+        assert!(settings.case_insensitive);
+        assert!(!settings.read_stdin);
+        assert_eq!(settings.available_options[1].index, 1);
+        assert_eq!(settings.available_options[1].value(), "key");
+        assert_eq!(settings.available_options[2].index, 2);
+        assert!(!settings.missing_options());
+
+        let settings = TMenuSettings::builder().option("a").password(true).build();
+        assert!(settings.available_options.is_empty());
+        assert!(settings.allow_undefined);
+        assert!(!settings.missing_options());
+        assert!(TMenuSettings::builder().build().missing_options());
     }
 }